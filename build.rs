@@ -0,0 +1,25 @@
+use std::fs::read_to_string;
+
+use toml::Value;
+
+// Passes the matrix-sdk revision from Cargo.toml on to the
+// version string so the two can't drift apart
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.toml");
+
+    let manifest = read_to_string("Cargo.toml")
+        .expect("Unable to read Cargo.toml.")
+        .parse::<Value>()
+        .expect("Unable to parse Cargo.toml.");
+
+    // Anything other than a git dependency, such as a local
+    // checkout, doesn't have a revision
+    let revision = manifest
+        .get("dependencies")
+        .and_then(|dependencies| dependencies.get("matrix-sdk"))
+        .and_then(|matrix_sdk| matrix_sdk.get("rev"))
+        .and_then(Value::as_str)
+        .unwrap_or("unknown");
+
+    println!("cargo:rustc-env=MATRIX_SDK_REV={}", revision);
+}
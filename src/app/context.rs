@@ -1,30 +1,81 @@
 use std::{
-    sync::mpsc::{self, Receiver, SendError, Sender},
-    time::Duration,
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
 };
 
-use tokio::task::JoinHandle;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{debug, warn};
 
 use super::{
-    ui::prelude::{Menu, Popup},
+    control::{ControlRequest, ControlResponse},
+    helper::Breakpoint,
+    keymap::{Action, Keymap},
+    room::RoomEntry,
+    theme::Theme,
+    ui::prelude::{Menu, Popup, PopupId, Toast},
     App,
 };
-use crate::client::{auth::AuthCreds, Client, ClientNotification};
+use crate::{
+    client::{
+        auth::AuthCreds, error::ClientError, hooks::HookEvent,
+        session::StoredSession, Client, ClientHandle, ClientNotification,
+        MessageKind,
+    },
+    config::Config,
+};
 
 pub enum Notification {
     QuitApplication(bool),
+    // Sent when the process is continued after being
+    // stopped so the screen is drawn from scratch
+    Redraw,
     SetLogin(AuthCreds),
     ShowPopup(Popup),
-    HidePopup,
+    HidePopup(PopupId),
+    ShowToast(Toast),
+    OpenRoom(String),
+    JoinRoom(String),
+    RunAction(Action),
     SwitchMenu(Box<dyn Menu + Send>),
-    ClientError(String),
+    ClientError(ClientError),
+    // Sent from the control socket, the response is sent
+    // back to the connection
+    Control(ControlRequest, Sender<ControlResponse>),
+    // A new message or invite, passed on to scripts
+    RoomEvent(HookEvent),
+    // Written in a prompt, rooms are given by ID
+    SendMessage {
+        room: String,
+        body: String,
+        kind: MessageKind,
+    },
+    // Confirmed by the user, the room is given by ID
+    RedactMessage(String),
+    // Sent by the client after the first sync
+    SetRooms(Vec<RoomEntry>),
+    // Rooms changed by a later sync and the IDs of any which
+    // were left
+    UpdateRooms(Vec<RoomEntry>, Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputMode {
+    Normal,
+    #[default]
+    Insert,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct ContextSettings {
     pub hide_help:        bool,
     pub quit_application: bool,
+    // Checked by the main loop as it owns the terminal
+    pub suspend:          bool,
+    pub redraw:           bool,
     pub login_details:    Option<AuthCreds>,
+    pub input_mode:       InputMode,
+    pub breakpoint:       Breakpoint,
+    pub current_room:     Option<String>,
 }
 
 impl ContextSettings {
@@ -35,48 +86,171 @@ impl ContextSettings {
 
 pub struct Context {
     notification_sender: Sender<Notification>,
-    client_notification_sender: Option<Sender<ClientNotification>>,
+    client_notification_sender: Option<UnboundedSender<ClientNotification>>,
     pub settings: ContextSettings,
+    pub config: Config,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub themes: Vec<Theme>,
+    pub rooms: Vec<RoomEntry>,
 }
 
 impl Context {
-    pub fn new() -> (Self, Receiver<Notification>) {
+    pub fn new(
+        config: Config,
+        themes: Vec<Theme>,
+    ) -> (Self, Receiver<Notification>) {
         let (notification_sender, notification_rec) = mpsc::channel();
 
+        let input_mode = if config.app.modal {
+            InputMode::Normal
+        } else {
+            InputMode::Insert
+        };
+        let settings = ContextSettings {
+            hide_help: config.app.hide_help,
+            input_mode,
+            ..Default::default()
+        };
+
+        let keymap = Keymap::new(&config.keybindings);
+
         let this = Self {
             notification_sender,
             client_notification_sender: None,
-            settings: ContextSettings::default(),
+            settings,
+            config,
+            keymap,
+            theme: Theme::default(),
+            themes,
+            rooms: Vec::new(),
         };
 
         (this, notification_rec)
     }
 
-    pub fn send_client_notification(
-        &self,
-        notification: ClientNotification,
-    ) -> Result<(), SendError<ClientNotification>> {
-        if let Some(sender) = &self.client_notification_sender {
-            sender.send(notification)
-        } else {
-            Ok(())
+    // Returns false if there is no theme with the name
+    pub fn set_theme(&mut self, name: &str) -> bool {
+        match self.themes.iter().find(|theme| theme.name == name) {
+            Some(theme) => {
+                self.theme = theme.clone();
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn next_theme(&mut self) {
+        let idx = self
+            .themes
+            .iter()
+            .position(|theme| theme.name == self.theme.name)
+            .map_or(0, |idx| (idx + 1) % self.themes.len());
+
+        if let Some(theme) = self.themes.get(idx) {
+            self.theme = theme.clone();
+        }
+    }
+
+    pub fn open_room(&mut self, id: &str) {
+        if let Some(room) = self.rooms.iter_mut().find(|room| room.id == id) {
+            debug!("Opened room {}", id);
+            room.last_viewed = Some(Instant::now());
+            self.settings.current_room = Some(id.to_string());
+        }
+    }
+
+    // Replaces the room list, keeping when each room was last
+    // viewed
+    pub fn set_rooms(&mut self, mut rooms: Vec<RoomEntry>) {
+        for room in rooms.iter_mut() {
+            room.last_viewed = self
+                .rooms
+                .iter()
+                .find(|old| old.id == room.id)
+                .and_then(|old| old.last_viewed);
+        }
+
+        self.rooms = rooms;
+    }
+
+    // Replaces the changed rooms, adds new ones and removes
+    // those which were left
+    pub fn update_rooms(&mut self, rooms: Vec<RoomEntry>, left: Vec<String>) {
+        self.rooms.retain(|room| !left.contains(&room.id));
+
+        for mut room in rooms {
+            match self.rooms.iter_mut().find(|old| old.id == room.id) {
+                Some(old) => {
+                    room.last_viewed = old.last_viewed;
+                    *old = room;
+                },
+                None => self.rooms.push(room),
+            }
+        }
+    }
+
+    // Rooms can be found by ID, alias or name
+    pub fn find_room(&self, query: &str) -> Option<&RoomEntry> {
+        self.rooms.iter().find(|room| room.id == query).or_else(|| {
+            self.rooms.iter().find(|room| {
+                room.alias.as_deref() == Some(query) || room.name == query
+            })
+        })
+    }
+
+    pub fn is_client_running(&self) -> bool {
+        self.client_notification_sender.is_some()
+    }
+
+    // Used by threads which need to notify the app
+    pub fn clone_sender(&self) -> Sender<Notification> {
+        self.notification_sender.clone()
+    }
+
+    // Notifications are dropped if the client hasn't been
+    // started yet
+    pub fn send_client_notification(&self, notification: ClientNotification) {
+        let sender = match &self.client_notification_sender {
+            Some(sender) => sender,
+            None => {
+                debug!("Client isn't running, dropping notification");
+                return;
+            },
+        };
+
+        if let Err(why) = sender.send(notification) {
+            warn!("Unable to send client notification: {}", why);
+        }
+    }
+
+    pub fn send_notification(&self, notification: Notification) {
+        if let Err(why) = self.notification_sender.send(notification) {
+            warn!("Unable to send notification: {}", why);
         }
     }
 
-    pub fn send_notification(
-        &self,
-        notification: Notification,
-    ) -> Result<(), SendError<Notification>> {
-        self.notification_sender.send(notification)
+    pub fn start_client(&mut self, credentials: AuthCreds) -> ClientHandle {
+        self.spawn_client(credentials, None)
     }
 
-    pub fn start_client(&mut self, credentials: AuthCreds) -> JoinHandle<()> {
+    // Logs in with the session saved by the last login, the
+    // login form is shown if it's no longer valid
+    pub fn restore_client(&mut self, session: StoredSession) -> ClientHandle {
+        self.spawn_client(AuthCreds::from_session(&session), Some(session))
+    }
+
+    fn spawn_client(
+        &mut self,
+        credentials: AuthCreds,
+        session: Option<StoredSession>,
+    ) -> ClientHandle {
         let sender = self.notification_sender.clone();
-        let (mut client, sender) = Client::new(credentials, sender);
-        let handle = tokio::task::spawn(async move { client.login().await });
+        let hooks = self.config.hooks.clone();
+        let (client, sender) = Client::new(credentials, session, hooks, sender);
 
         self.client_notification_sender = Some(sender);
-        handle
+        client.spawn()
     }
 }
 
@@ -85,3 +259,46 @@ pub fn handle_notification(receiver: &Receiver<Notification>, app: &mut App) {
         app.on_notification(notification);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::app::{
+        room::{RoomEntry, RoomKind},
+        ui::widget::test::context,
+    };
+
+    fn room(id: &str, unread: u64) -> RoomEntry {
+        RoomEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            alias: None,
+            kind: RoomKind::Room,
+            members: Vec::new(),
+            unread,
+            latest_event: None,
+            last_viewed: None,
+        }
+    }
+
+    #[test]
+    fn update_rooms_only_touches_changed_rooms() {
+        let (mut context, _receiver) = context();
+        context.set_rooms(vec![room("!a", 1), room("!b", 2), room("!c", 3)]);
+        let viewed = Instant::now();
+        context.rooms[0].last_viewed = Some(viewed);
+
+        context.update_rooms(vec![room("!a", 5), room("!d", 1)], vec![
+            "!b".to_string()
+        ]);
+
+        let rooms = context
+            .rooms
+            .iter()
+            .map(|room| (room.id.as_str(), room.unread))
+            .collect::<Vec<(&str, u64)>>();
+        assert_eq!(rooms, vec![("!a", 5), ("!c", 3), ("!d", 1)]);
+        assert_eq!(context.rooms[0].last_viewed, Some(viewed));
+    }
+}
@@ -0,0 +1,388 @@
+#[cfg(unix)]
+use std::{
+    fs::{remove_file, set_permissions, DirBuilder, Permissions},
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use std::{path::PathBuf, sync::mpsc::Sender};
+
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use tracing::{debug, info, warn};
+
+use super::{context::Notification, room::RoomEntry};
+use crate::error::{Error, Result};
+#[cfg(unix)]
+use crate::fs::{socket_directory, socket_path};
+
+// How long a connection waits for the app to handle a
+// request before giving up
+#[cfg(unix)]
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Each line sent to the socket is one request, the kind of
+// request is given by the `request` field, for example
+// `{"request": "open_room", "room": "#konoha:matrix.org"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "request",
+    rename_all = "snake_case",
+    deny_unknown_fields
+)]
+pub enum ControlRequest {
+    OpenRoom {
+        room: String,
+    },
+    SendMessage {
+        room: String,
+        body: String,
+    },
+    // Files are read by the app so the path should be
+    // absolute
+    Upload {
+        room: String,
+        path: PathBuf,
+    },
+    // Joined rooms, invites aren't included
+    Rooms,
+    UnreadCounts,
+    MarkRead {
+        room: String,
+    },
+    Quit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomUnread {
+    pub id:     String,
+    pub name:   String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias:  Option<String>,
+    pub unread: u64,
+}
+
+// Sent back as a single line for every request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok:    bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rooms: Option<Vec<RoomUnread>>,
+}
+
+impl ControlResponse {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn unread<'a, I>(rooms: I) -> Self
+    where
+        I: IntoIterator<Item = &'a RoomEntry>, {
+        let rooms = rooms
+            .into_iter()
+            .map(|room| RoomUnread {
+                id:     room.id.clone(),
+                name:   room.name.clone(),
+                alias:  room.alias.clone(),
+                unread: room.unread,
+            })
+            .collect();
+
+        Self {
+            ok: true,
+            rooms: Some(rooms),
+            ..Default::default()
+        }
+    }
+}
+
+// Removes the socket when dropped so that other instances
+// don't have to check whether it's stale
+pub struct ControlSocket {
+    #[cfg(unix)]
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+// Listens for requests on a socket in the runtime
+// directory, each request is sent to the app as a
+// notification and the connection waits for the response
+#[cfg(unix)]
+pub fn spawn_control_listener(
+    sender: Sender<Notification>,
+) -> Result<ControlSocket> {
+    // The socket is created inside a directory only the user
+    // can open, so nobody else can connect between binding it
+    // and changing its permissions
+    let dir = socket_directory()?;
+    DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+    set_permissions(&dir, Permissions::from_mode(0o700))?;
+
+    listen(socket_path()?, sender)
+}
+
+// Split out so that tests can use their own socket
+#[cfg(unix)]
+fn listen(
+    path: PathBuf,
+    sender: Sender<Notification>,
+) -> Result<ControlSocket> {
+    if path.exists() {
+        // Sockets left behind by an instance which crashed
+        // can't be connected to
+        if UnixStream::connect(&path).is_ok() {
+            return Err(Error::OtherError(format!(
+                "Another instance is already listening on '{}'.",
+                path.display()
+            )));
+        }
+        remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path).map_err(|why| {
+        Error::OtherError(format!("Unable to create control socket.\n{}", why))
+    })?;
+    let socket = ControlSocket {
+        path: path.clone(),
+    };
+    info!("Listening for control requests on {}", path.display());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let sender = sender.clone();
+                    thread::spawn(move || handle_connection(stream, sender));
+                },
+                Err(why) => warn!("Unable to accept connection: {}", why),
+            }
+        }
+    });
+
+    Ok(socket)
+}
+
+#[cfg(not(unix))]
+pub fn spawn_control_listener(
+    _sender: Sender<Notification>,
+) -> Result<ControlSocket> {
+    Ok(ControlSocket {})
+}
+
+// Sends a single request to the instance listening on the
+// socket, used to forward commands to a running instance
+#[cfg(unix)]
+pub fn send_request(request: &ControlRequest) -> Result<ControlResponse> {
+    send_request_to(&socket_path()?, request)
+}
+
+#[cfg(unix)]
+fn send_request_to(
+    path: &Path,
+    request: &ControlRequest,
+) -> Result<ControlResponse> {
+    let stream = UnixStream::connect(path).map_err(|why| {
+        Error::OtherError(format!(
+            "Unable to connect to '{}', is the control socket \
+             enabled?\n{}",
+            path.display(),
+            why
+        ))
+    })?;
+    // Allow for the app's own timeout before giving up
+    stream.set_read_timeout(Some(RESPONSE_TIMEOUT * 2))?;
+
+    let text = serde_json::to_string(request).map_err(|why| {
+        Error::OtherError(format!("Unable to serialize request.\n{}", why))
+    })?;
+    writeln!(&stream, "{}", text)?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    serde_json::from_str(&line)
+        .map_err(|why| Error::OtherError(format!("Invalid response.\n{}", why)))
+}
+
+#[cfg(not(unix))]
+pub fn send_request(_request: &ControlRequest) -> Result<ControlResponse> {
+    Err(Error::OtherError(
+        "The control socket is only supported on Unix.".to_string(),
+    ))
+}
+
+#[cfg(unix)]
+fn handle_connection(stream: UnixStream, sender: Sender<Notification>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(why) => {
+            warn!("Unable to write to control connection: {}", why);
+            return;
+        },
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => forward_request(request, &sender),
+            Err(why) => {
+                ControlResponse::error(format!("Invalid request: {}", why))
+            },
+        };
+
+        let text = match serde_json::to_string(&response) {
+            Ok(text) => text,
+            Err(why) => {
+                warn!("Unable to serialize control response: {}", why);
+                break;
+            },
+        };
+        if writeln!(writer, "{}", text).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn forward_request(
+    request: ControlRequest,
+    sender: &Sender<Notification>,
+) -> ControlResponse {
+    debug!("Received control request {:?}", request);
+
+    let (response_sender, receiver) = mpsc::channel();
+    let notification = Notification::Control(request, response_sender);
+    if sender.send(notification).is_err() {
+        return ControlResponse::error("The app is shutting down.");
+    }
+
+    receiver
+        .recv_timeout(RESPONSE_TIMEOUT)
+        .unwrap_or_else(|_| ControlResponse::error("The app didn't respond."))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        env,
+        fs::{create_dir_all, remove_dir_all},
+        process,
+    };
+
+    use super::*;
+    use crate::{
+        app::{
+            room::{RoomEntry, RoomKind},
+            script::Scripts,
+            ui::widget::test::context,
+            App,
+        },
+        client::auth::AuthCreds,
+    };
+
+    fn room(id: &str, name: &str, kind: RoomKind) -> RoomEntry {
+        RoomEntry {
+            id: id.to_string(),
+            name: name.to_string(),
+            alias: None,
+            kind,
+            members: Vec::new(),
+            unread: 2,
+            latest_event: None,
+            last_viewed: None,
+        }
+    }
+
+    #[test]
+    fn requests_are_answered_by_the_app() {
+        let dir = env::temp_dir()
+            .join(format!("konoha-control-test-{}", process::id()));
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("konoha.sock");
+
+        let (context, receiver) = context();
+        let _socket = listen(path.clone(), context.clone_sender()).unwrap();
+        let mut app = App::new(context, Scripts::new(), AuthCreds::default());
+        app.context.set_rooms(vec![
+            room("!general:example.org", "General", RoomKind::Room),
+            room("!invite:example.org", "Invite", RoomKind::Invite),
+        ]);
+
+        let requests = [
+            ControlRequest::Rooms,
+            ControlRequest::SendMessage {
+                room: "General".to_string(),
+                body: "Hello".to_string(),
+            },
+            ControlRequest::MarkRead {
+                room: "Unknown".to_string(),
+            },
+            ControlRequest::MarkRead {
+                room: "General".to_string(),
+            },
+        ];
+        let count = requests.len();
+        let client = thread::spawn(move || {
+            requests
+                .iter()
+                .map(|request| send_request_to(&path, request).unwrap())
+                .collect::<Vec<ControlResponse>>()
+        });
+
+        // Stands in for the main loop
+        for _ in 0..count {
+            let notification = receiver.recv_timeout(RESPONSE_TIMEOUT).unwrap();
+            app.on_notification(notification);
+        }
+        let responses = client.join().unwrap();
+        remove_dir_all(&dir).unwrap();
+
+        let rooms = responses[0].rooms.as_ref().unwrap();
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(rooms[0].name, "General");
+        assert_eq!(rooms[0].unread, 2);
+        // Nothing is sent before logging in
+        assert_eq!(responses[1].error.as_deref(), Some("Not logged in."));
+        assert_eq!(
+            responses[2].error.as_deref(),
+            Some("Unknown room 'Unknown'.")
+        );
+        // Nothing has been received to send a receipt for
+        assert_eq!(
+            responses[3].error.as_deref(),
+            Some("Nothing has been received in 'General' to mark as read.")
+        );
+        assert_eq!(app.context.find_room("General").unwrap().unread, 2);
+    }
+}
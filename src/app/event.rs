@@ -1,17 +1,31 @@
 use std::{
-    sync::mpsc::{self, Receiver},
+    sync::mpsc::{self, Receiver, Sender},
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
 use crossterm::event::{self, Event as CTEvent, KeyEvent, MouseEvent};
+#[cfg(unix)]
+use signal_hook::{
+    consts::{SIGCONT, SIGHUP, SIGINT, SIGTERM, SIGTSTP},
+    iterator::Signals,
+};
+use tracing::info;
 
-use super::App;
+use super::{context::Notification, keymap::Action, App};
+use crate::error::Result;
+#[cfg(unix)]
+use crate::{error::Error, logging, terminal};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Event {
+    // Sent instead of `Key` when the key press matches a
+    // binding of one of the menu's actions
+    Action(Action),
     Key(KeyEvent),
     Mouse(MouseEvent),
+    // The new width and height of the terminal
+    Resize(u16, u16),
     Tick,
 }
 
@@ -19,7 +33,9 @@ pub fn handle_event(receiver: &Receiver<Event>, app: &mut App) {
     if let Ok(event) = receiver.recv_timeout(Duration::ZERO) {
         match event {
             Event::Key(key) => app.on_key_press(key),
+            Event::Action(action) => app.on_action(action),
             Event::Mouse(event) => app.on_mouse(event),
+            Event::Resize(width, height) => app.on_resize(width, height),
             Event::Tick => app.on_tick(),
         }
     }
@@ -50,7 +66,9 @@ pub fn spawn_event_listener(tick_ms: u64) -> EventReceiver {
                         let term_event = match event {
                             CTEvent::Key(event) => Event::Key(event),
                             CTEvent::Mouse(event) => Event::Mouse(event),
-                            CTEvent::Resize(..) => continue,
+                            CTEvent::Resize(width, height) => {
+                                Event::Resize(width, height)
+                            },
                         };
 
                         if let Err(_why) = sr.send(term_event) {
@@ -81,3 +99,48 @@ pub fn spawn_event_listener(tick_ms: u64) -> EventReceiver {
         receiver,
     }
 }
+
+// Asks the app to quit on SIGTERM, SIGHUP or SIGINT. If the
+// app doesn't quit before another quit signal is received
+// the terminal is restored and the process exits
+// immediately. SIGTSTP and SIGCONT suspend and redraw the
+// app
+#[cfg(unix)]
+pub fn spawn_signal_listener(sender: Sender<Notification>) -> Result<()> {
+    let signals = [SIGTERM, SIGHUP, SIGINT, SIGTSTP, SIGCONT];
+    let mut signals = Signals::new(signals).map_err(|why| {
+        Error::OtherError(format!("Unable to listen for signals.\n{}", why))
+    })?;
+
+    thread::spawn(move || {
+        let mut quitting = false;
+
+        for signal in signals.forever() {
+            let notification = match signal {
+                SIGTSTP => Notification::RunAction(Action::Suspend),
+                SIGCONT => Notification::Redraw,
+                _ if quitting => {
+                    terminal::restore();
+                    logging::flush();
+                    std::process::exit(128 + signal);
+                },
+                _ => {
+                    info!("Received signal {}, quitting", signal);
+                    quitting = true;
+                    Notification::QuitApplication(false)
+                },
+            };
+
+            if sender.send(notification).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn spawn_signal_listener(_sender: Sender<Notification>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,110 @@
+// Scoring for fuzzy matching where the characters of the
+// query have to appear in order but not next to each other
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 16;
+const WORD_START_BONUS: i64 = 24;
+const PREFIX_BONUS: i64 = 32;
+const GAP_PENALTY: i64 = 2;
+const EXACT_BONUS: i64 = 100;
+
+// Returns None if the query doesn't match, otherwise a
+// score where higher is a better match
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query = query.to_lowercase().chars().collect::<Vec<char>>();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let text = text.to_lowercase().chars().collect::<Vec<char>>();
+    let mut score = 0;
+    let mut query_idx = 0;
+    let mut last_match: Option<usize> = None;
+
+    for (idx, c) in text.iter().enumerate() {
+        if query_idx == query.len() {
+            break;
+        }
+
+        if *c != query[query_idx] {
+            continue;
+        }
+
+        score += MATCH_SCORE;
+        match last_match {
+            Some(last) if last + 1 == idx => score += CONSECUTIVE_BONUS,
+            Some(last) => score -= GAP_PENALTY * (idx - last - 1) as i64,
+            None if idx == 0 => score += PREFIX_BONUS,
+            None => score -= GAP_PENALTY * idx as i64,
+        }
+
+        let word_start = idx == 0 || !text[idx - 1].is_alphanumeric();
+        if word_start {
+            score += WORD_START_BONUS;
+        }
+
+        last_match = Some(idx);
+        query_idx += 1;
+    }
+
+    if query_idx < query.len() {
+        return None;
+    }
+
+    if query == text {
+        score += EXACT_BONUS;
+    }
+
+    // Prefer shorter text when everything else is equal
+    Some(score - (text.len() - query.len()) as i64 / 4)
+}
+
+// Best score from any of the texts
+pub fn best_score<'a, I>(query: &str, texts: I) -> Option<i64>
+where
+    I: IntoIterator<Item = &'a str>, {
+    texts
+        .into_iter()
+        .filter_map(|text| fuzzy_score(query, text))
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closer_matches_score_higher() {
+        let exact = fuzzy_score("dev", "dev").unwrap();
+        let prefix = fuzzy_score("dev", "devops").unwrap();
+        let word_start = fuzzy_score("dev", "rust dev").unwrap();
+        let inside_word = fuzzy_score("dev", "abcdev").unwrap();
+
+        assert!(exact > prefix);
+        assert!(prefix > word_start);
+        assert!(word_start > inside_word);
+    }
+
+    #[test]
+    fn ignores_case() {
+        assert_eq!(fuzzy_score("DEV", "devops"), fuzzy_score("dev", "DevOps"));
+    }
+
+    #[test]
+    fn characters_have_to_be_in_order() {
+        assert_eq!(fuzzy_score("xyz", "general"), None);
+        assert_eq!(fuzzy_score("ba", "abc"), None);
+        assert_eq!(fuzzy_score("generals", "general"), None);
+        assert!(fuzzy_score("gnrl", "general").is_some());
+        assert_eq!(fuzzy_score("", "general"), Some(0));
+    }
+
+    #[test]
+    fn best_score_uses_best_text() {
+        let texts = ["abcdev", "devops", "other"];
+        assert_eq!(
+            best_score("dev", texts.iter().copied()),
+            fuzzy_score("dev", "devops")
+        );
+        assert_eq!(best_score("xyz", texts.iter().copied()), None);
+    }
+}
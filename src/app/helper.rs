@@ -1,14 +1,14 @@
 use std::io::Stdout;
 
-use clap::crate_name;
-use crossterm::event::{KeyCode, KeyModifiers};
 use tui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    widgets::{Block, Borders, Paragraph},
+    widgets::Paragraph,
     Frame,
 };
 
+use super::theme::Theme;
+
 pub type CrosstermFrame<'a> = Frame<'a, CrosstermBackend<Stdout>>;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -30,6 +30,31 @@ impl Spacing {
     }
 }
 
+// Widths at which layouts should change so that the app
+// stays usable in small terminals
+const NARROW_WIDTH: u16 = 60;
+const WIDE_WIDTH: u16 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Breakpoint {
+    Narrow,
+    #[default]
+    Medium,
+    Wide,
+}
+
+impl Breakpoint {
+    pub fn from_width(width: u16) -> Self {
+        if width < NARROW_WIDTH {
+            Self::Narrow
+        } else if width < WIDE_WIDTH {
+            Self::Medium
+        } else {
+            Self::Wide
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum CenterPosition {
     Percentage(u16, u16),
@@ -41,25 +66,25 @@ pub fn centered_rect(position: CenterPosition, base: Rect) -> Rect {
     let (constraints_x, constraints_y) = match position {
         CenterPosition::AbsoluteInner(x, y) => (
             [
-                Constraint::Length((base.width - x) / 2),
+                Constraint::Length(base.width.saturating_sub(x) / 2),
                 Constraint::Length(x),
-                Constraint::Length((base.width - x) / 2),
+                Constraint::Length(base.width.saturating_sub(x) / 2),
             ],
             [
-                Constraint::Length((base.height - y) / 2),
+                Constraint::Length(base.height.saturating_sub(y) / 2),
                 Constraint::Length(y),
-                Constraint::Length((base.height - y) / 2),
+                Constraint::Length(base.height.saturating_sub(y) / 2),
             ],
         ),
         CenterPosition::AbsoluteOutter(x, y) => (
             [
                 Constraint::Length(x),
-                Constraint::Length(base.width - (x * 2)),
+                Constraint::Length(base.width.saturating_sub(x * 2)),
                 Constraint::Length(x),
             ],
             [
                 Constraint::Length(y),
-                Constraint::Length(base.height - (y * 2)),
+                Constraint::Length(base.height.saturating_sub(y * 2)),
                 Constraint::Length(y),
             ],
         ),
@@ -95,14 +120,16 @@ pub fn centered_line(
 ) -> Rect {
     let (constraints_x, constraints_y) = (
         [
-            Constraint::Length((base.width - width) / 2),
+            Constraint::Length(base.width.saturating_sub(width) / 2),
             Constraint::Length(width),
-            Constraint::Length((base.width - width) / 2),
+            Constraint::Length(base.width.saturating_sub(width) / 2),
         ],
         [
             Constraint::Length(top_padding),
             Constraint::Length(height),
-            Constraint::Length(base.height - height - top_padding),
+            Constraint::Length(
+                base.height.saturating_sub(height + top_padding),
+            ),
         ],
     );
 
@@ -133,9 +160,16 @@ pub fn split_rect(
     [split[0], split[1]]
 }
 
+pub fn area_contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x
+        && column < area.x + area.width
+        && row >= area.y
+        && row < area.y + area.height
+}
+
 pub fn expand_area(mut area: Rect, spacing: Spacing) -> Rect {
-    area.x -= spacing.left;
-    area.y -= spacing.top;
+    area.x = area.x.saturating_sub(spacing.left);
+    area.y = area.y.saturating_sub(spacing.top);
     area.width += spacing.left + spacing.right;
     area.height += spacing.top + spacing.bottom;
 
@@ -145,8 +179,8 @@ pub fn expand_area(mut area: Rect, spacing: Spacing) -> Rect {
 pub fn shrink_area(mut area: Rect, spacing: Spacing) -> Rect {
     area.x += spacing.left;
     area.y += spacing.top;
-    area.width -= spacing.left + spacing.right;
-    area.height -= spacing.top + spacing.bottom;
+    area.width = area.width.saturating_sub(spacing.left + spacing.right);
+    area.height = area.height.saturating_sub(spacing.top + spacing.bottom);
 
     area
 }
@@ -156,44 +190,17 @@ pub fn shrink_area(mut area: Rect, spacing: Spacing) -> Rect {
 // drawn
 pub fn draw_help_menu(
     frame: &mut CrosstermFrame,
-    mut menu_help: Vec<(KeyModifiers, KeyCode, String)>,
+    menu_help: Vec<(String, String)>,
     max_size: Rect,
+    theme: &Theme,
 ) -> Rect {
-    menu_help.insert(
-        0,
-        (
-            KeyModifiers::CONTROL,
-            KeyCode::Char('h'),
-            "Toggle help menu".to_string(),
-        ),
-    );
-    menu_help.insert(
-        1,
-        (
-            KeyModifiers::CONTROL,
-            KeyCode::Char('d'),
-            format!("Exit {}", crate_name!()),
-        ),
-    );
-    let mapped = menu_help.into_iter().map(|(mods, key, msg)| {
-        let mut mod_str = String::new();
-        if mods.contains(KeyModifiers::ALT) {
-            mod_str += "Alt+";
-        }
-        if mods.contains(KeyModifiers::CONTROL) {
-            mod_str += "Ctrl+";
-        }
-        if mods.contains(KeyModifiers::SHIFT) {
-            mod_str += "Shft+";
-        }
-
-        mod_str += format!("{} - {}", keycode_to_str(key), msg).as_ref();
-
-        mod_str
-    });
+    let mapped = menu_help
+        .into_iter()
+        .map(|(keys, msg)| format!("{} - {}", keys, msg));
     let seperator = ", ";
     let mut text = mapped.collect::<Vec<String>>().join(seperator);
-    let mut split = split_text(&text, seperator, max_size.width as usize - 6);
+    let mut split =
+        split_text(&text, seperator, max_size.width.saturating_sub(6) as usize);
 
     let mut lines = split.len() as u16;
     let mut longest = split
@@ -204,7 +211,8 @@ pub fn draw_help_menu(
 
     if longest + 4 > max_size.width {
         text = "Size too small to draw".to_string();
-        split = split_text(&text, " ", max_size.width as usize - 4);
+        split =
+            split_text(&text, " ", max_size.width.saturating_sub(4) as usize);
 
         lines = split.len() as u16;
         longest = split
@@ -214,6 +222,12 @@ pub fn draw_help_menu(
             .unwrap_or_else(|| text.len()) as u16;
     }
 
+    // Leave the space to the menu if even the error won't
+    // fit, e.g. in a small tmux split
+    if longest + 4 > max_size.width || lines + 2 >= max_size.height {
+        return max_size;
+    }
+
     let layouts = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Min(0), Constraint::Length(lines + 2)])
@@ -224,7 +238,7 @@ pub fn draw_help_menu(
     // Append 4 extra; 2 for padding and 2 for the border
     let layout = centered_line(longest + 4, lines + 2, 0, bottom);
 
-    let help_block = Block::default().title("Help").borders(Borders::ALL);
+    let help_block = theme.block().title("Help");
     frame.render_widget(help_block, layout);
 
     for (idx, line) in split.iter().enumerate() {
@@ -237,30 +251,12 @@ pub fn draw_help_menu(
     top
 }
 
-fn keycode_to_str(key: KeyCode) -> String {
-    match key {
-        KeyCode::F(x) => format!("F{}", x),
-        KeyCode::Char(x) => x.to_string().to_uppercase(),
-        KeyCode::Up => "Up".to_string(),
-        KeyCode::Down => "Down".to_string(),
-        KeyCode::Left => "Left".to_string(),
-        KeyCode::Right => "Right".to_string(),
-        KeyCode::Delete => "Delete".to_string(),
-        KeyCode::End => "End".to_string(),
-        KeyCode::Enter => "Enter".to_string(),
-        KeyCode::Esc => "Escape".to_string(),
-        KeyCode::Home => "Home".to_string(),
-        KeyCode::Insert => "Insert".to_string(),
-        KeyCode::PageUp => "Page Up".to_string(),
-        KeyCode::PageDown => "Page Down".to_string(),
-        KeyCode::Tab => "Tab".to_string(),
-        KeyCode::BackTab => "Backtab".to_string(),
-        KeyCode::Backspace => "Backspace".to_string(),
-        KeyCode::Null => String::default(),
+pub fn split_text(text: &str, sep: &str, max_size: usize) -> Vec<String> {
+    // Nothing can fit so there's no point splitting
+    if max_size == 0 {
+        return vec![text.to_string()];
     }
-}
 
-pub fn split_text(text: &str, sep: &str, max_size: usize) -> Vec<String> {
     let mut output = Vec::new();
     let mut input_remaining = text.to_string();
 
@@ -277,6 +273,11 @@ pub fn split_text(text: &str, sep: &str, max_size: usize) -> Vec<String> {
         } else if remaining.starts_with(' ') || split.ends_with(' ') {
             output.push(split.trim_matches(' ').to_string());
             input_remaining = remaining.trim_matches(' ').to_string();
+        } else {
+            // The word is longer than the line so it has to be
+            // split in the middle
+            output.push(split.to_string());
+            input_remaining = remaining.to_string();
         }
     }
 
@@ -284,3 +285,39 @@ pub fn split_text(text: &str, sep: &str, max_size: usize) -> Vec<String> {
 
     output
 }
+
+// Number of rows the text takes up when it's word wrapped
+// to the width in the same way as a wrapped `Paragraph`
+pub fn wrapped_height(text: &str, width: u16) -> usize {
+    let width = width as usize;
+    if width == 0 {
+        return 0;
+    }
+
+    text.split('\n')
+        .map(|line| {
+            let mut rows = 1;
+            let mut row_width = 0;
+
+            for word in line.split(' ') {
+                let word_width = word.chars().count();
+                if row_width == 0 {
+                    row_width = word_width;
+                } else if row_width + 1 + word_width <= width {
+                    row_width += 1 + word_width;
+                } else {
+                    rows += 1;
+                    row_width = word_width;
+                }
+
+                // Words longer than a row are split across rows
+                while row_width > width {
+                    rows += 1;
+                    row_width -= width;
+                }
+            }
+
+            rows
+        })
+        .sum()
+}
@@ -0,0 +1,695 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    result::Result as StdResult,
+    str::FromStr,
+};
+
+use clap::crate_name;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer};
+
+// Every action which can be bound to a key. Menus return
+// the actions they currently respond to and receive them
+// as `Event::Action` once the keymap resolves a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    ToggleHelp,
+    ShowKeybindings,
+    Quit,
+    FocusNext,
+    FocusPrevious,
+    SelectLeft,
+    SelectRight,
+    Submit,
+    ClosePopup,
+    NextTheme,
+    ShowNotifications,
+    ShowLogs,
+    QuickSwitcher,
+    CommandPalette,
+    Suspend,
+    NormalMode,
+    InsertMode,
+    CommandLine,
+    MoveUp,
+    MoveDown,
+    JumpTop,
+    JumpBottom,
+    Search,
+    Reply,
+    Edit,
+    Redact,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Toggle,
+}
+
+impl Action {
+    pub const ALL: [Action; 31] = [
+        Action::ToggleHelp,
+        Action::ShowKeybindings,
+        Action::Quit,
+        Action::FocusNext,
+        Action::FocusPrevious,
+        Action::SelectLeft,
+        Action::SelectRight,
+        Action::Submit,
+        Action::ClosePopup,
+        Action::NextTheme,
+        Action::ShowNotifications,
+        Action::ShowLogs,
+        Action::QuickSwitcher,
+        Action::CommandPalette,
+        Action::Suspend,
+        Action::NormalMode,
+        Action::InsertMode,
+        Action::CommandLine,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::JumpTop,
+        Action::JumpBottom,
+        Action::Search,
+        Action::Reply,
+        Action::Edit,
+        Action::Redact,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::Toggle,
+    ];
+    // Actions which are handled by the app and are always
+    // available regardless of the current menu
+    pub const GLOBAL: [Action; 9] = [
+        Action::ToggleHelp,
+        Action::Quit,
+        Action::ShowKeybindings,
+        Action::NextTheme,
+        Action::ShowNotifications,
+        Action::ShowLogs,
+        Action::QuickSwitcher,
+        Action::CommandPalette,
+        Action::Suspend,
+    ];
+    // Actions which are only available in normal mode as
+    // their bindings would otherwise conflict with typing
+    pub const NORMAL: [Action; 10] = [
+        Action::InsertMode,
+        Action::CommandLine,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::JumpTop,
+        Action::JumpBottom,
+        Action::Search,
+        Action::Reply,
+        Action::Edit,
+        Action::Redact,
+    ];
+
+    // Name used to reference the action in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ToggleHelp => "toggle_help",
+            Self::ShowKeybindings => "show_keybindings",
+            Self::Quit => "quit",
+            Self::FocusNext => "focus_next",
+            Self::FocusPrevious => "focus_previous",
+            Self::SelectLeft => "select_left",
+            Self::SelectRight => "select_right",
+            Self::Submit => "submit",
+            Self::ClosePopup => "close_popup",
+            Self::NextTheme => "next_theme",
+            Self::ShowNotifications => "show_notifications",
+            Self::ShowLogs => "show_logs",
+            Self::QuickSwitcher => "quick_switcher",
+            Self::CommandPalette => "command_palette",
+            Self::Suspend => "suspend",
+            Self::NormalMode => "normal_mode",
+            Self::InsertMode => "insert_mode",
+            Self::CommandLine => "command_line",
+            Self::MoveUp => "move_up",
+            Self::MoveDown => "move_down",
+            Self::JumpTop => "jump_top",
+            Self::JumpBottom => "jump_bottom",
+            Self::Search => "search",
+            Self::Reply => "reply",
+            Self::Edit => "edit",
+            Self::Redact => "redact",
+            Self::PageUp => "page_up",
+            Self::PageDown => "page_down",
+            Self::First => "first",
+            Self::Last => "last",
+            Self::Toggle => "toggle",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Self::ToggleHelp => "Toggle help menu".to_string(),
+            Self::ShowKeybindings => "Show keybindings".to_string(),
+            Self::Quit => format!("Exit {}", crate_name!()),
+            Self::FocusNext => "Select next".to_string(),
+            Self::FocusPrevious => "Select previous".to_string(),
+            Self::SelectLeft => "Select left".to_string(),
+            Self::SelectRight => "Select right".to_string(),
+            Self::Submit => "Confirm selection".to_string(),
+            Self::ClosePopup => "Close popup".to_string(),
+            Self::NextTheme => "Next theme".to_string(),
+            Self::ShowNotifications => "Notifications".to_string(),
+            Self::ShowLogs => "Logs".to_string(),
+            Self::QuickSwitcher => "Switch room".to_string(),
+            Self::CommandPalette => "Command palette".to_string(),
+            Self::Suspend => "Suspend".to_string(),
+            Self::NormalMode => "Normal mode".to_string(),
+            Self::InsertMode => "Insert mode".to_string(),
+            Self::CommandLine => "Command line".to_string(),
+            Self::MoveUp => "Move up".to_string(),
+            Self::MoveDown => "Move down".to_string(),
+            Self::JumpTop => "Jump to top".to_string(),
+            Self::JumpBottom => "Jump to bottom".to_string(),
+            Self::Search => "Search rooms".to_string(),
+            Self::Reply => "Reply".to_string(),
+            Self::Edit => "Edit last message".to_string(),
+            Self::Redact => "Redact last message".to_string(),
+            Self::PageUp => "Page up".to_string(),
+            Self::PageDown => "Page down".to_string(),
+            Self::First => "Select first".to_string(),
+            Self::Last => "Select last".to_string(),
+            Self::Toggle => "Toggle selection".to_string(),
+        }
+    }
+
+    fn default_bindings(&self) -> Vec<KeySequence> {
+        let keys: &[&str] = match self {
+            Self::ToggleHelp => &["Ctrl+h"],
+            Self::ShowKeybindings => &["F1"],
+            Self::Quit => &["Ctrl+d"],
+            Self::FocusNext => &["Tab", "Down"],
+            Self::FocusPrevious => &["BackTab", "Up"],
+            Self::SelectLeft => &["Left"],
+            Self::SelectRight => &["Right"],
+            Self::Submit => &["Enter"],
+            Self::ClosePopup => &["Esc"],
+            Self::NextTheme => &["F2"],
+            Self::ShowNotifications => &["F3"],
+            Self::ShowLogs => &["F4"],
+            Self::QuickSwitcher => &["Ctrl+k"],
+            Self::CommandPalette => &["Ctrl+p"],
+            Self::Suspend => &["Ctrl+z"],
+            Self::NormalMode => &["Esc"],
+            Self::InsertMode => &["i"],
+            Self::CommandLine => &[":"],
+            Self::MoveUp => &["k"],
+            Self::MoveDown => &["j"],
+            Self::JumpTop => &["g g"],
+            Self::JumpBottom => &["G"],
+            Self::Search => &["/"],
+            Self::Reply => &["r"],
+            Self::Edit => &["e"],
+            Self::Redact => &["d d"],
+            Self::PageUp => &["PageUp"],
+            Self::PageDown => &["PageDown"],
+            Self::First => &["Home"],
+            Self::Last => &["End"],
+            Self::Toggle => &["Space"],
+        };
+
+        keys.iter()
+            .map(|key| key.parse().expect("Invalid default keybinding."))
+            .collect()
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(name: &str) -> StdResult<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|action| action.name() == name)
+            .copied()
+            .ok_or_else(|| format!("unknown action '{}'", name))
+    }
+}
+
+impl<'de> Deserialize<'de> for Action {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>, {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub modifiers: KeyModifiers,
+    pub code:      KeyCode,
+}
+
+impl KeyBinding {
+    pub fn new(modifiers: KeyModifiers, code: KeyCode) -> Self {
+        Self {
+            modifiers,
+            code,
+        }
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        match (self.code, key.code) {
+            // Shift is already applied to the character so it
+            // shouldn't have to be specified in the binding
+            (KeyCode::Char(bind), KeyCode::Char(pressed)) => {
+                bind == pressed
+                    && self.modifiers - KeyModifiers::SHIFT
+                        == key.modifiers - KeyModifiers::SHIFT
+            },
+            (bind, pressed) => {
+                bind == pressed && self.modifiers == key.modifiers
+            },
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(text: &str) -> StdResult<Self, Self::Err> {
+        let mut parts = text.split('+').collect::<Vec<&str>>();
+        // Allows binding '+' by itself or with modifiers
+        if text.ends_with("++") || text == "+" {
+            parts.pop();
+            parts.pop();
+            parts.push("+");
+        }
+
+        let key = parts
+            .pop()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| format!("missing key in '{}'", text))?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_ref() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" | "shft" => KeyModifiers::SHIFT,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier '{}' in '{}'",
+                        modifier, text
+                    ))
+                },
+            };
+        }
+
+        let code = match key.to_lowercase().as_ref() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            lower => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => {
+                        let num = lower
+                            .strip_prefix('f')
+                            .and_then(|num| num.parse::<u8>().ok())
+                            .ok_or_else(|| {
+                                format!("unknown key '{}' in '{}'", key, text)
+                            })?;
+
+                        KeyCode::F(num)
+                    },
+                }
+            },
+        };
+
+        Ok(Self::new(modifiers, code))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shft+")?;
+        }
+
+        match self.code {
+            KeyCode::F(x) => write!(f, "F{}", x),
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(x) => f.write_char(x),
+            KeyCode::Up => f.write_str("Up"),
+            KeyCode::Down => f.write_str("Down"),
+            KeyCode::Left => f.write_str("Left"),
+            KeyCode::Right => f.write_str("Right"),
+            KeyCode::Delete => f.write_str("Delete"),
+            KeyCode::End => f.write_str("End"),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Esc => f.write_str("Escape"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::Insert => f.write_str("Insert"),
+            KeyCode::PageUp => f.write_str("Page Up"),
+            KeyCode::PageDown => f.write_str("Page Down"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::BackTab => f.write_str("Backtab"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Null => Ok(()),
+        }
+    }
+}
+
+// One or more keys which have to be pressed in order,
+// written as space separated bindings e.g. "g g"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyBinding>);
+
+impl KeySequence {
+    fn starts_with(&self, keys: &[KeyEvent]) -> bool {
+        self.0.len() >= keys.len()
+            && self.0.iter().zip(keys).all(|(bind, key)| bind.matches(key))
+    }
+
+    // Sequences starting with a character would conflict
+    // with typing so they only work in normal mode
+    fn types_text(&self) -> bool {
+        self.0.first().is_some_and(|bind| {
+            matches!(bind.code, KeyCode::Char(_))
+                && (bind.modifiers - KeyModifiers::SHIFT).is_empty()
+        })
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(text: &str) -> StdResult<Self, Self::Err> {
+        let keys = text
+            .split_whitespace()
+            .map(KeyBinding::from_str)
+            .collect::<StdResult<Vec<KeyBinding>, String>>()?;
+
+        if keys.is_empty() {
+            return Err("empty keybinding".to_string());
+        }
+
+        Ok(Self(keys))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, key) in self.0.iter().enumerate() {
+            if idx != 0 {
+                f.write_char(' ')?;
+            }
+            fmt::Display::fmt(key, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeySequence {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>, {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapResult {
+    Matched(Action),
+    // Keys bound by a script, the index is the position of
+    // the binding in `Keymap::bind_scripts`
+    Script(usize),
+    // The key is part of a longer sequence
+    Pending,
+    NoMatch,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeySequence>)>,
+    // Keys and a description of what they run
+    scripts:  Vec<(KeySequence, String)>,
+    pending:  Vec<KeyEvent>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&HashMap::new())
+    }
+}
+
+impl Keymap {
+    // Any action specified in `overrides` will have all of
+    // its default bindings replaced
+    pub fn new(overrides: &HashMap<Action, Vec<KeySequence>>) -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|action| {
+                let keys = overrides
+                    .get(action)
+                    .cloned()
+                    .unwrap_or_else(|| action.default_bindings());
+
+                (*action, keys)
+            })
+            .collect();
+
+        Self {
+            bindings,
+            scripts: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn bind_scripts(&mut self, bindings: Vec<(KeySequence, String)>) {
+        self.scripts = bindings;
+    }
+
+    pub fn get_bindings(&self, action: Action) -> &[KeySequence] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    // Resolve a key press against the actions currently
+    // available, keeping track of partially typed
+    // sequences
+    pub fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        actions: &[Action],
+    ) -> KeymapResult {
+        self.resolve_key(key, actions, None)
+    }
+
+    // Same as `handle_key` but also matches keys bound by
+    // scripts, actions take priority if both match. Like
+    // `Action::NORMAL`, bindings which would conflict with
+    // typing are skipped outside of normal mode
+    pub fn handle_key_with_scripts(
+        &mut self,
+        key: KeyEvent,
+        actions: &[Action],
+        normal_mode: bool,
+    ) -> KeymapResult {
+        self.resolve_key(key, actions, Some(normal_mode))
+    }
+
+    // Script bindings are only included when given whether
+    // the app is in normal mode
+    fn resolve_key(
+        &mut self,
+        key: KeyEvent,
+        actions: &[Action],
+        scripts: Option<bool>,
+    ) -> KeymapResult {
+        self.pending.push(key);
+
+        let script_candidates = match scripts {
+            Some(normal_mode) => self
+                .scripts
+                .iter()
+                .map(|(keys, _)| keys)
+                .enumerate()
+                .filter(|(_, keys)| normal_mode || !keys.types_text())
+                .collect(),
+            None => Vec::new(),
+        };
+        let candidates = self
+            .bindings
+            .iter()
+            .filter(|(action, _)| actions.contains(action))
+            .flat_map(|(action, sequences)| {
+                sequences
+                    .iter()
+                    .map(move |keys| (keys, KeymapResult::Matched(*action)))
+            })
+            .chain(
+                script_candidates
+                    .into_iter()
+                    .map(|(index, keys)| (keys, KeymapResult::Script(index))),
+            );
+
+        let mut exact = None;
+        let mut partial = false;
+        for (sequence, result) in candidates {
+            if !sequence.starts_with(&self.pending) {
+                continue;
+            }
+
+            if sequence.0.len() == self.pending.len() {
+                exact = exact.or(Some(result));
+            } else {
+                partial = true;
+            }
+        }
+
+        if let Some(result) = exact {
+            self.pending.clear();
+            return result;
+        }
+
+        if partial {
+            return KeymapResult::Pending;
+        }
+
+        // Retry with just the new key if it broke a sequence
+        // so that it isn't swallowed
+        let broke_sequence = self.pending.len() > 1;
+        self.pending.clear();
+        if broke_sequence {
+            return self.resolve_key(key, actions, scripts);
+        }
+
+        KeymapResult::NoMatch
+    }
+
+    // Pairs of the primary binding and description used by
+    // the help bar
+    pub fn get_help(&self, actions: &[Action]) -> Vec<(String, String)> {
+        actions
+            .iter()
+            .filter_map(|action| {
+                self.get_bindings(*action)
+                    .first()
+                    .map(|keys| (keys.to_string(), action.description()))
+            })
+            .collect()
+    }
+
+    // Full list of every action and all of its bindings
+    pub fn get_reference(&self) -> String {
+        let mut reference = String::new();
+
+        for (idx, (action, sequences)) in self.bindings.iter().enumerate() {
+            if idx != 0 {
+                reference.push('\n');
+            }
+
+            let keys = if sequences.is_empty() {
+                "Unbound".to_string()
+            } else {
+                sequences
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+
+            let _ = write!(
+                reference,
+                "{} ({}): {}",
+                action.description(),
+                action.name(),
+                keys
+            );
+        }
+
+        for (keys, description) in &self.scripts {
+            let _ = write!(reference, "\nScript {}: {}", description, keys);
+        }
+
+        reference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn keymap() -> Keymap {
+        let mut keymap = Keymap::default();
+        keymap.bind_scripts(vec![
+            ("x".parse().unwrap(), "typed".to_string()),
+            ("ctrl+x".parse().unwrap(), "modified".to_string()),
+        ]);
+        keymap
+    }
+
+    #[test]
+    fn script_keys_which_type_need_normal_mode() {
+        let mut keymap = keymap();
+        let result =
+            keymap.handle_key_with_scripts(key(KeyCode::Char('x')), &[], false);
+        assert!(matches!(result, KeymapResult::NoMatch));
+
+        let result =
+            keymap.handle_key_with_scripts(key(KeyCode::Char('x')), &[], true);
+        assert!(matches!(result, KeymapResult::Script(0)));
+    }
+
+    #[test]
+    fn script_keys_with_modifiers_work_in_insert_mode() {
+        let mut keymap = keymap();
+        let ctrl_x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        let result = keymap.handle_key_with_scripts(ctrl_x, &[], false);
+        assert!(matches!(result, KeymapResult::Script(1)));
+    }
+
+    #[test]
+    fn redact_needs_both_keys() {
+        let mut keymap = Keymap::default();
+        let actions = Action::NORMAL;
+        let d = key(KeyCode::Char('d'));
+
+        let result = keymap.handle_key_with_scripts(d, &actions, true);
+        assert!(matches!(result, KeymapResult::Pending));
+        let result = keymap.handle_key_with_scripts(d, &actions, true);
+        assert!(matches!(result, KeymapResult::Matched(Action::Redact)));
+    }
+}
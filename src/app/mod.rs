@@ -1,205 +1,1023 @@
-use std::io::stdout;
-
-use crossterm::{
-    event::{
-        DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent,
-        KeyModifiers, MouseEvent,
-    },
-    execute,
-    terminal::{
-        disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
-        LeaveAlternateScreen,
-    },
+use std::{
+    collections::HashMap, io::stdout, path::PathBuf,
+    result::Result as StdResult, time::Duration,
 };
-use tokio::task::JoinHandle;
+
+use clap::{crate_name, crate_version};
+use crossterm::event::{KeyEvent, MouseEvent};
+use tracing::{debug, error, info, level_filters::LevelFilter, warn};
 use tui::{
     backend::CrosstermBackend,
-    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Clear, Paragraph},
     Terminal,
 };
 
 use self::{
-    context::{Context, Notification},
+    context::{Context, InputMode, Notification},
+    control::{spawn_control_listener, ControlRequest, ControlResponse},
     event::Event,
-    helper::{draw_help_menu, expand_area, split_text, CrosstermFrame},
+    helper::{
+        draw_help_menu, expand_area, split_text, Breakpoint, CrosstermFrame,
+    },
+    keymap::{Action, KeymapResult},
+    room::{RoomEntry, RoomKind},
+    script::{ScriptEffect, ScriptOutput, Scripts},
+    theme::load_themes,
     ui::prelude::{
-        message::PopupMessageBuilder, new_confirm_popup, AuthenticateMenu,
-        Menu, Popup,
+        message::PopupMessageBuilder, new_confirm_popup, new_log_popup,
+        new_palette_popup, new_switcher_popup, prompt::PopupPromptBuilder,
+        AuthenticateMenu, InputWidget, Menu, Popup, Severity, Toast, Toasts,
+        ValidationType, Widget,
     },
 };
 use crate::{
     app::{
         context::handle_notification,
-        event::{handle_event, spawn_event_listener},
+        event::{handle_event, spawn_event_listener, spawn_signal_listener},
         helper::Spacing,
     },
-    error::Result,
+    client::{
+        auth::AuthCreds, session::load_session, ClientHandle,
+        ClientNotification, MessageKind,
+    },
+    config::Config,
+    error::{has_panicked, set_app_running, set_current_view, Error, Result},
+    logging,
+    terminal::{self, TerminalGuard},
 };
 
 pub mod context;
+pub mod control;
 pub mod event;
+mod fuzzy;
 mod helper;
+pub mod keymap;
+pub mod room;
+mod script;
+pub mod theme;
 pub mod ui;
 
+// Time given to the client to finish saving before it's
+// aborted when quitting
+const CLIENT_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct App {
-    pub client_handle: Option<JoinHandle<()>>,
+    pub client_handle: Option<ClientHandle>,
     pub context:       Context,
     pub menu:          Box<dyn Menu + Send>,
-    pub popup:         Option<Popup>,
+    // Popups in the order they were shown, the last popup is
+    // drawn on top and receives input
+    pub popups:        Vec<Popup>,
+    pub toasts:        Toasts,
+    pub command_line:  Option<InputWidget>,
+    pub scripts:       Scripts,
+    // Set while `on_send` filters run, see `send_message`
+    filtering_send:    bool,
+    // Last message sent to each room by ID, edits start from
+    // this text
+    last_sent:         HashMap<String, String>,
 }
 
 impl App {
-    pub fn new(context: Context) -> Self {
+    pub fn new(
+        context: Context,
+        scripts: Scripts,
+        credentials: AuthCreds,
+    ) -> Self {
         Self {
             context,
+            scripts,
             client_handle: None,
-            menu: Box::new(AuthenticateMenu::default()),
-            popup: None,
+            menu: Box::new(AuthenticateMenu::new(credentials)),
+            popups: Vec::new(),
+            toasts: Toasts::default(),
+            command_line: None,
+            filtering_send: false,
+            last_sent: HashMap::new(),
         }
     }
 
     pub fn draw(&mut self, frame: &mut CrosstermFrame) {
-        let area = if !self.context.settings.hide_help {
-            let help_message = if let Some(popup) = &mut self.popup {
-                popup.get_help_message(&self.context)
-            } else {
-                self.menu.get_help_message(&self.context)
-            };
+        let mut area = frame.size();
+        frame.render_widget(
+            Paragraph::new("").style(self.context.theme.base_style()),
+            area,
+        );
 
-            draw_help_menu(frame, help_message, frame.size())
-        } else {
-            frame.size()
-        };
+        if self.context.config.app.modal {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(1)])
+                .split(area);
 
-        let (min_width, min_height) = self.menu.get_minimum_size();
-        if min_width > area.width || min_height > area.height {
-            let text = split_text(
-                "Please resize your screen so there is more space to draw!",
-                " ",
-                area.width as usize - 2,
-            )
-            .join("\n");
+            self.draw_status_line(frame, split[1]);
+            area = split[0];
+        }
 
-            let block = Block::default().title("Error").borders(Borders::ALL);
-            let error = Paragraph::new(text).block(block);
-            frame.render_widget(error, area);
-        } else {
+        if !self.context.settings.hide_help {
+            let actions = self.get_actions();
+            let help_message = self.context.keymap.get_help(&actions);
+
+            area =
+                draw_help_menu(frame, help_message, area, &self.context.theme);
+        }
+
+        if fits(self.menu.get_minimum_size(), area) {
             self.menu.draw(frame, area, &self.context);
+        } else {
+            let block = self.context.theme.block().title("Error");
+            frame.render_widget(
+                Paragraph::new(too_small_text(area)).block(block),
+                area,
+            );
         }
 
-        if let Some(popup) = &mut self.popup {
+        for popup in self.popups.iter_mut() {
             let popup_area = popup.get_area(area);
-            let popup_block = Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded);
-
             let popup_border =
                 expand_area(popup_area, Spacing::new(1, 1, 1, 1));
             frame.render_widget(Clear, popup_border);
-            frame.render_widget(popup_block, popup_border);
 
-            popup.draw(frame, popup_area, &self.context);
+            if fits(popup.get_minimum_size(), popup_area) {
+                let popup_block = self.context.theme.popup_block();
+                frame.render_widget(popup_block, popup_border);
+
+                popup.draw(frame, popup_area, &self.context);
+            } else {
+                let text = too_small_text(popup_border);
+                let block = self.context.theme.popup_block().title("Error");
+                frame.render_widget(
+                    Paragraph::new(text).block(block),
+                    popup_border,
+                );
+            }
+        }
+
+        self.toasts.draw(frame, area, &self.context.theme);
+    }
+
+    // Shows either the current input mode or the command
+    // line if it's open
+    fn draw_status_line(&mut self, frame: &mut CrosstermFrame, area: Rect) {
+        if let Some(command_line) = &mut self.command_line {
+            let split = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(area);
+
+            frame.render_widget(Paragraph::new(":"), split[0]);
+            command_line.render(split[1], frame, &self.context);
+        } else {
+            let mode = match self.context.settings.input_mode {
+                InputMode::Normal => "-- NORMAL --",
+                InputMode::Insert => "-- INSERT --",
+            };
+
+            frame.render_widget(Paragraph::new(mode), area);
         }
     }
 
+    fn is_normal_mode(&self) -> bool {
+        self.context.config.app.modal
+            && self.popups.is_empty()
+            && self.context.settings.input_mode == InputMode::Normal
+    }
+
+    // Global actions followed by the actions of the focused
+    // popup or menu
+    fn get_actions(&mut self) -> Vec<Action> {
+        let mut actions = Action::GLOBAL.to_vec();
+        let mut menu_actions = if let Some(popup) = self.popups.last_mut() {
+            popup.get_actions(&self.context)
+        } else {
+            self.menu.get_actions(&self.context)
+        };
+
+        // Popups ignore the input mode as they have their own
+        // way of being closed
+        if self.is_normal_mode() {
+            actions.extend_from_slice(&[
+                Action::InsertMode,
+                Action::CommandLine,
+                Action::Search,
+                Action::Reply,
+                Action::Edit,
+                Action::Redact,
+            ]);
+        } else {
+            menu_actions.retain(|action| !Action::NORMAL.contains(action));
+
+            if self.context.config.app.modal && self.popups.is_empty() {
+                actions.push(Action::NormalMode);
+            }
+        }
+
+        actions.append(&mut menu_actions);
+        actions
+    }
+
     pub fn on_key_press(&mut self, key: KeyEvent) {
-        if key.code == KeyCode::Char('h') && key.modifiers == KeyModifiers::CONTROL
-        {
-            self.context.settings.toggle_help();
+        if let Some(command_line) = &mut self.command_line {
+            let actions = [Action::Submit, Action::NormalMode];
+            match self.context.keymap.handle_key(key, &actions) {
+                KeymapResult::Matched(Action::Submit) => {
+                    let command = command_line.value.clone();
+                    self.command_line = None;
+                    self.run_command(&command);
+                },
+                KeymapResult::Matched(_) => self.command_line = None,
+                KeymapResult::Pending | KeymapResult::Script(_) => {},
+                KeymapResult::NoMatch => {
+                    command_line.on_key(&self.context, key);
+                },
+            }
+
             return;
         }
 
-        if key.code == KeyCode::Char('d')
-            && key.modifiers == KeyModifiers::CONTROL
-        {
-            // TODO: Logging
-            let _ = self
-                .context
-                .send_notification(Notification::QuitApplication(true));
-            return;
+        let actions = self.get_actions();
+        let normal_mode = self.is_normal_mode();
+
+        match self.context.keymap.handle_key_with_scripts(
+            key,
+            &actions,
+            normal_mode,
+        ) {
+            KeymapResult::Matched(action) => self.on_action(action),
+            KeymapResult::Script(index) => {
+                let output = self.scripts.run_key_binding(index);
+                self.apply_script_output(output);
+            },
+            KeymapResult::Pending => {},
+            KeymapResult::NoMatch => {
+                // Keys are only passed on in insert mode so that
+                // typing in normal mode doesn't edit anything
+                if self.is_normal_mode() {
+                    return;
+                }
+
+                if let Some(popup) = self.popups.last_mut() {
+                    popup.on_event(Event::Key(key), &self.context);
+                } else {
+                    self.menu.on_event(Event::Key(key), &self.context);
+                }
+            },
         }
+    }
+
+    pub fn on_action(&mut self, action: Action) {
+        match action {
+            Action::ToggleHelp => self.context.settings.toggle_help(),
+            Action::Quit => {
+                self.context
+                    .send_notification(Notification::QuitApplication(true));
+            },
+            Action::ShowKeybindings => {
+                let popup = PopupMessageBuilder::new(
+                    self.context.keymap.get_reference(),
+                )
+                .set_title(Some("Keybindings"))
+                .set_modal(false)
+                .to_popup();
+
+                self.context
+                    .send_notification(Notification::ShowPopup(popup));
+            },
+            Action::NextTheme => {
+                self.context.next_theme();
+
+                let message =
+                    format!("Theme set to {}.", self.context.theme.name);
+                self.toasts.push(Toast::new(message, Severity::Info));
+            },
+            Action::ShowNotifications => {
+                let popup = self.toasts.history_popup();
 
-        if let Some(popup) = &mut self.popup {
-            popup.on_event(Event::Key(key), &self.context);
+                self.context
+                    .send_notification(Notification::ShowPopup(popup));
+            },
+            Action::ShowLogs => {
+                self.context.send_notification(Notification::ShowPopup(
+                    new_log_popup(),
+                ));
+            },
+            Action::CommandPalette => {
+                let actions = self.get_actions();
+                let popup = new_palette_popup(&self.context.keymap, &actions);
+
+                self.context
+                    .send_notification(Notification::ShowPopup(popup));
+            },
+            Action::QuickSwitcher => {
+                let popup = new_switcher_popup(self.context.rooms.clone());
+
+                self.context
+                    .send_notification(Notification::ShowPopup(popup));
+            },
+            Action::Suspend => self.context.settings.suspend = true,
+            Action::NormalMode => {
+                self.context.settings.input_mode = InputMode::Normal;
+            },
+            Action::InsertMode => {
+                self.context.settings.input_mode = InputMode::Insert;
+            },
+            Action::CommandLine => {
+                self.command_line =
+                    Some(InputWidget::default().set_selected(true).to_owned());
+            },
+            // Rooms are the only thing listed so they're what is
+            // searched
+            Action::Search => self.on_action(Action::QuickSwitcher),
+            Action::Reply => self.show_compose_prompt(MessageKind::Reply),
+            Action::Edit => self.show_compose_prompt(MessageKind::Edit),
+            Action::Redact => self.show_redact_confirm(),
+            _ => {
+                if let Some(popup) = self.popups.last_mut() {
+                    popup.on_event(Event::Action(action), &self.context);
+                } else {
+                    self.menu.on_event(Event::Action(action), &self.context);
+                }
+            },
+        }
+    }
+
+    pub fn run_command(&mut self, command: &str) {
+        let (name, args) = command
+            .trim()
+            .split_once(' ')
+            .map_or((command.trim(), ""), |(name, args)| (name, args.trim()));
+
+        let error = match name {
+            "" => None,
+            "q" | "quit" => {
+                self.on_action(Action::Quit);
+                None
+            },
+            "q!" | "quit!" => {
+                self.context
+                    .send_notification(Notification::QuitApplication(false));
+                None
+            },
+            "help" => {
+                self.on_action(Action::ToggleHelp);
+                None
+            },
+            "keys" | "keybindings" => {
+                self.on_action(Action::ShowKeybindings);
+                None
+            },
+            "notifications" => {
+                self.on_action(Action::ShowNotifications);
+                None
+            },
+            "logs" => {
+                self.on_action(Action::ShowLogs);
+                None
+            },
+            "theme" => {
+                if args.is_empty() {
+                    self.on_action(Action::NextTheme);
+                    None
+                } else if !self.context.set_theme(args) {
+                    Some(format!("Unknown theme '{}'.", args))
+                } else {
+                    None
+                }
+            },
+            // Commands registered by scripts can't replace the
+            // built in ones
+            command => match self.scripts.run_command(command, args) {
+                Some(output) => {
+                    self.apply_script_output(output);
+                    None
+                },
+                None => Some(format!("Unknown command ':{}'.", command)),
+            },
+        };
+
+        if let Some(error) = error {
+            debug!("Command ':{}' failed: {}", command, error);
+            self.toasts.push(Toast::new(error, Severity::Error));
+        }
+    }
+
+    pub fn apply_script_output(&mut self, output: ScriptOutput) {
+        for error in output.errors {
+            warn!("{}", error);
+            self.toasts.push(Toast::new(error, Severity::Error));
+        }
+
+        for effect in output.effects {
+            match effect {
+                ScriptEffect::SendMessage {
+                    room,
+                    body,
+                } => {
+                    let result = match self.context.find_room(&room) {
+                        Some(entry) => {
+                            let id = entry.id.clone();
+                            self.send_message(id, body, MessageKind::New)
+                        },
+                        None => Err(format!("Unknown room '{}'.", room)),
+                    };
+
+                    if let Err(message) = result {
+                        self.toasts.push(Toast::new(message, Severity::Error));
+                    }
+                },
+                ScriptEffect::Notify(message) => {
+                    self.toasts.push(Toast::new(message, Severity::Info));
+                },
+                ScriptEffect::OpenRoom(room) => {
+                    match self.context.find_room(&room) {
+                        Some(entry) => {
+                            let id = entry.id.clone();
+                            self.context.open_room(&id);
+                        },
+                        None => {
+                            let message = format!("Unknown room '{}'.", room);
+                            self.toasts
+                                .push(Toast::new(message, Severity::Error));
+                        },
+                    }
+                },
+                ScriptEffect::RunAction(action) => self.on_action(action),
+            }
+        }
+    }
+
+    // Every message goes through here so that scripts can
+    // change or cancel it. Messages sent by the filters
+    // themselves aren't filtered again so they can't loop
+    fn send_message(
+        &mut self,
+        room: String,
+        body: String,
+        kind: MessageKind,
+    ) -> StdResult<(), String> {
+        if !self.context.is_client_running() {
+            return Err("Not logged in.".to_string());
+        }
+
+        let body = if self.filtering_send {
+            Some(body)
         } else {
-            self.menu.on_event(Event::Key(key), &self.context);
+            self.filtering_send = true;
+            let (body, output) = self.scripts.filter_outgoing(&room, body);
+            self.apply_script_output(output);
+            self.filtering_send = false;
+            body
+        };
+        let body =
+            body.ok_or_else(|| "A script cancelled the message.".to_string())?;
+
+        self.last_sent.insert(room.clone(), body.clone());
+        self.context.send_client_notification(
+            ClientNotification::SendMessage {
+                room,
+                body,
+                kind,
+            },
+        );
+        Ok(())
+    }
+
+    // The room which replies, edits and redactions act on
+    fn current_room(&self) -> StdResult<&RoomEntry, String> {
+        if !self.context.is_client_running() {
+            return Err("Not logged in.".to_string());
         }
+
+        self.context
+            .settings
+            .current_room
+            .as_ref()
+            .and_then(|id| {
+                self.context.rooms.iter().find(|room| &room.id == id)
+            })
+            .ok_or_else(|| "Open a room first.".to_string())
+    }
+
+    // Replies go to the newest message in the open room and
+    // edits replace the last message sent to it, without a
+    // timeline to pick from these are the messages acted on
+    fn show_compose_prompt(&mut self, kind: MessageKind) {
+        let room = match self.current_room() {
+            Ok(room) => room,
+            Err(message) => {
+                return self.toasts.push(Toast::new(message, Severity::Warning))
+            },
+        };
+
+        let (title, value) = match kind {
+            MessageKind::Edit => match self.last_sent.get(&room.id) {
+                Some(body) => (format!("Edit in {}", room.name), body.clone()),
+                None => {
+                    let message =
+                        format!("Nothing has been sent to {}.", room.name);
+                    return self
+                        .toasts
+                        .push(Toast::new(message, Severity::Warning));
+                },
+            },
+            MessageKind::Reply => {
+                (format!("Reply in {}", room.name), String::new())
+            },
+            MessageKind::New => {
+                (format!("Message to {}", room.name), String::new())
+            },
+        };
+
+        let id = room.id.clone();
+        let popup = PopupPromptBuilder::new(title)
+            .add_field("body", "Message")
+            .set_value("body", value)
+            .set_validation(
+                "body",
+                ValidationType::Functional(|body| !body.trim().is_empty()),
+                "The message can't be empty.",
+            )
+            .set_submit("Send")
+            .to_owned()
+            .to_popup(move |ctx, mut values| {
+                ctx.send_notification(Notification::SendMessage {
+                    room: id,
+                    body: values.remove("body").unwrap_or_default(),
+                    kind,
+                });
+            });
+
+        self.context
+            .send_notification(Notification::ShowPopup(popup));
+    }
+
+    fn show_redact_confirm(&mut self) {
+        let room = match self.current_room() {
+            Ok(room) => room,
+            Err(message) => {
+                return self.toasts.push(Toast::new(message, Severity::Warning))
+            },
+        };
+        if !self.last_sent.contains_key(&room.id) {
+            let message = format!("Nothing has been sent to {}.", room.name);
+            return self.toasts.push(Toast::new(message, Severity::Warning));
+        }
+
+        let id = room.id.clone();
+        let popup = new_confirm_popup(
+            format!("Redact your last message in {}?", room.name),
+            move |ctx| ctx.send_notification(Notification::RedactMessage(id)),
+        );
+
+        self.context
+            .send_notification(Notification::ShowPopup(popup));
     }
 
     pub fn on_mouse(&mut self, event: MouseEvent) {
-        if let Some(popup) = &mut self.popup {
+        if let Some(popup) = self.popups.last_mut() {
             popup.on_event(Event::Mouse(event), &self.context);
         } else {
             self.menu.on_event(Event::Mouse(event), &self.context);
         }
     }
 
+    pub fn on_resize(&mut self, width: u16, height: u16) {
+        self.context.settings.breakpoint = Breakpoint::from_width(width);
+
+        for popup in self.popups.iter_mut() {
+            popup.on_event(Event::Resize(width, height), &self.context);
+        }
+
+        self.menu
+            .on_event(Event::Resize(width, height), &self.context);
+    }
+
     pub fn on_tick(&mut self) {
-        if let Some(popup) = &mut self.popup {
+        if let Some(command_line) = &mut self.command_line {
+            command_line.on_tick(&self.context);
+        }
+
+        self.toasts.on_tick();
+
+        // Tick popups from the top down, stopping at the first
+        // modal popup
+        for popup in self.popups.iter_mut().rev() {
             popup.on_event(Event::Tick, &self.context);
+
+            if popup.is_modal() {
+                return;
+            }
         }
 
         self.menu.on_event(Event::Tick, &self.context);
     }
 
+    // Waits for the client to stop syncing and close its
+    // store so that nothing is lost
+    pub fn shutdown(&mut self) {
+        if let Some(handle) = self.client_handle.take() {
+            info!("Stopping client");
+            self.context
+                .send_client_notification(ClientNotification::Shutdown);
+            handle.wait(CLIENT_STOP_TIMEOUT);
+        }
+    }
+
+    // Records the open menu and popups for crash reports
+    fn update_view(&self) {
+        let view = std::iter::once(self.menu.get_name())
+            .chain(self.popups.iter().map(|popup| popup.get_name()))
+            .collect::<Vec<&str>>()
+            .join(" > ");
+
+        set_current_view(view);
+    }
+
     pub fn on_notification(&mut self, notification: Notification) {
         match notification {
             Notification::QuitApplication(show_confirm) => {
                 if show_confirm {
-                    self.popup = Some(new_confirm_popup(
+                    self.popups.push(new_confirm_popup(
                         "Are you sure you want to exit?",
                         |ctx| {
-                            // TODO: Logging
-                            let _ = ctx.send_notification(
+                            ctx.send_notification(
                                 Notification::QuitApplication(false),
                             );
                         },
                     ))
                 } else {
+                    info!("Quitting");
                     self.context.settings.quit_application = true;
                 }
             },
+            Notification::Redraw => self.context.settings.redraw = true,
             Notification::SetLogin(login) => {
+                info!(
+                    "Starting client for @{}:{}",
+                    login.username, login.homeserver
+                );
                 self.client_handle = Some(self.context.start_client(login))
             },
-            Notification::ShowPopup(popup) => self.popup = Some(popup),
-            Notification::HidePopup => self.popup = None,
+            Notification::ShowPopup(popup) => self.popups.push(popup),
+            Notification::HidePopup(id) => {
+                self.popups.retain(|popup| popup.id() != id);
+            },
+            Notification::ShowToast(toast) => self.toasts.push(toast),
+            Notification::OpenRoom(id) => self.context.open_room(&id),
+            Notification::RunAction(action) => self.on_action(action),
+            Notification::JoinRoom(alias) => {
+                info!("Joining {}", alias);
+                let message = format!("Joining {}.", alias);
+                self.toasts.push(Toast::new(message, Severity::Info));
+
+                self.context.send_client_notification(
+                    ClientNotification::JoinRoom(alias),
+                );
+            },
             Notification::SwitchMenu(menu) => self.menu = menu,
             Notification::ClientError(why) => {
-                let popup = PopupMessageBuilder::new(why)
-                    .set_title(Some("Error"))
+                error!("{} {}\n{}", why.context, why.reason(), why.details());
+
+                let title = if why.is_retryable() {
+                    "Temporary Error"
+                } else {
+                    "Error"
+                };
+                let mut message = why.to_string();
+                if self.context.config.client.verbose {
+                    message = format!("{}\n\n{}", message, why.details());
+                }
+
+                let popup = PopupMessageBuilder::new(message)
+                    .set_title(Some(title))
                     .to_popup();
 
-                // TODO: Logging
-                let _ = self
-                    .context
+                self.context
                     .send_notification(Notification::ShowPopup(popup));
             },
+            Notification::Control(request, sender) => {
+                let response = self.on_control_request(request);
+                if sender.send(response).is_err() {
+                    debug!("Control connection closed before responding");
+                }
+            },
+            Notification::RoomEvent(event) => {
+                let output = self.scripts.on_event(&event);
+                self.apply_script_output(output);
+            },
+            Notification::SendMessage {
+                room,
+                body,
+                kind,
+            } => {
+                if let Err(message) = self.send_message(room, body, kind) {
+                    self.toasts.push(Toast::new(message, Severity::Error));
+                }
+            },
+            Notification::RedactMessage(room) => {
+                self.last_sent.remove(&room);
+                self.context
+                    .send_client_notification(ClientNotification::Redact(room));
+            },
+            Notification::SetRooms(rooms) => self.context.set_rooms(rooms),
+            Notification::UpdateRooms(rooms, left) => {
+                self.context.update_rooms(rooms, left)
+            },
+        }
+
+        self.update_view();
+    }
+
+    fn on_control_request(
+        &mut self,
+        request: ControlRequest,
+    ) -> ControlResponse {
+        let room_id = match &request {
+            ControlRequest::OpenRoom {
+                room,
+            }
+            | ControlRequest::SendMessage {
+                room,
+                ..
+            }
+            | ControlRequest::Upload {
+                room,
+                ..
+            }
+            | ControlRequest::MarkRead {
+                room,
+            } => match self.context.find_room(room) {
+                Some(entry) => Some(entry.id.clone()),
+                None => {
+                    return ControlResponse::error(format!(
+                        "Unknown room '{}'.",
+                        room
+                    ))
+                },
+            },
+            _ => None,
+        };
+
+        match request {
+            ControlRequest::OpenRoom {
+                ..
+            } => {
+                if let Some(id) = room_id {
+                    self.context.open_room(&id);
+                }
+            },
+            ControlRequest::SendMessage {
+                body,
+                ..
+            } => {
+                if let Some(room) = room_id {
+                    if let Err(message) =
+                        self.send_message(room, body, MessageKind::New)
+                    {
+                        return ControlResponse::error(message);
+                    }
+                }
+            },
+            ControlRequest::Upload {
+                path,
+                ..
+            } => {
+                if !self.context.is_client_running() {
+                    return ControlResponse::error("Not logged in.");
+                }
+
+                if let Some(room) = room_id {
+                    self.context.send_client_notification(
+                        ClientNotification::Upload {
+                            room,
+                            path,
+                        },
+                    );
+                }
+            },
+            ControlRequest::Rooms => {
+                let joined = self
+                    .context
+                    .rooms
+                    .iter()
+                    .filter(|room| room.kind != RoomKind::Invite);
+                return ControlResponse::unread(joined);
+            },
+            ControlRequest::UnreadCounts => {
+                return ControlResponse::unread(&self.context.rooms);
+            },
+            ControlRequest::MarkRead {
+                room,
+            } => {
+                if let Some(id) = room_id {
+                    // Receipts are sent for the newest event, the count
+                    // is left alone when there isn't one to send
+                    let has_event = self.context.rooms.iter().any(|room| {
+                        room.id == id && room.latest_event.is_some()
+                    });
+                    if !has_event {
+                        return ControlResponse::error(format!(
+                            "Nothing has been received in '{}' to mark as \
+                             read.",
+                            room
+                        ));
+                    }
+                    if !self.context.is_client_running() {
+                        return ControlResponse::error("Not logged in.");
+                    }
+
+                    if let Some(room) =
+                        self.context.rooms.iter_mut().find(|room| room.id == id)
+                    {
+                        room.unread = 0;
+                    }
+                    self.context.send_client_notification(
+                        ClientNotification::MarkRead(id),
+                    );
+                }
+            },
+            ControlRequest::Quit => {
+                self.on_notification(Notification::QuitApplication(false));
+            },
+        }
+
+        ControlResponse::ok()
+    }
+}
+
+// Stops the client when the app returns through an error or
+// unwinds from a panic so the store is still closed
+impl Drop for App {
+    fn drop(&mut self) {
+        self.shutdown();
+        set_app_running(false);
+    }
+}
+
+fn fits((width, height): (u16, u16), area: Rect) -> bool {
+    width <= area.width && height <= area.height
+}
+
+// Shown in place of a menu or popup which doesn't have
+// enough space to be drawn
+fn too_small_text(area: Rect) -> String {
+    split_text(
+        "Please resize your screen so there is more space to draw!",
+        " ",
+        area.width.saturating_sub(2) as usize,
+    )
+    .join("\n")
+}
+
+// Errors from the same source are shown together in one
+// popup, in the order they happened
+fn show_startup_errors(context: &Context, errors: &[(&str, Error)]) {
+    let mut titles = Vec::new();
+    for (title, _) in errors {
+        if !titles.contains(title) {
+            titles.push(*title);
         }
     }
+
+    for title in titles {
+        let message = errors
+            .iter()
+            .filter(|(source, _)| *source == title)
+            .map(|(_, why)| why.to_string())
+            .collect::<Vec<String>>()
+            .join("\n\n");
+        let popup = PopupMessageBuilder::new(message)
+            .set_title(Some(title))
+            .to_popup();
+
+        context.send_notification(Notification::ShowPopup(popup));
+    }
+}
+
+// Options from the command line, these take priority over
+// the config
+#[derive(Debug, Default)]
+pub struct AppOptions {
+    pub config_path: Option<PathBuf>,
+    pub log_level:   Option<LevelFilter>,
+    pub verbose:     bool,
+    pub no_mouse:    bool,
+    // Used to fill in the login form
+    pub credentials: AuthCreds,
 }
 
-pub fn start_app() -> Result<()> {
-    let (context, noti_rec) = Context::new();
+pub fn start_app(options: AppOptions) -> Result<()> {
+    // Fallback to the defaults so that any errors can be
+    // shown in a popup once the app has started
+    let config_path = options.config_path.as_deref();
+    // Each error is shown under the title of where it came from
+    let mut errors: Vec<(&str, Error)> = Vec::new();
+    let mut config = Config::load(config_path).unwrap_or_else(|why| {
+        errors.push(("Config Error", why));
+        Config::default()
+    });
+    if let Some(level) = options.log_level {
+        config.log.level = level;
+    }
+    if options.verbose {
+        config.client.verbose = true;
+    }
+    let tick_rate = config.app.tick_rate;
+    let theme = config.app.theme.clone();
+
+    // Flushes the remaining logs when dropped at the end
+    let _log_guard = match logging::init(&config.log) {
+        Ok(guard) => Some(guard),
+        Err(why) => {
+            errors.push(("Logging Error", why));
+            None
+        },
+    };
+    info!("Starting {} v{}", crate_name!(), crate_version!());
+
+    let (themes, theme_errors) = load_themes();
+    errors.extend(theme_errors.into_iter().map(|why| ("Theme Error", why)));
+
+    let (mut context, noti_rec) = Context::new(config, themes);
+    if !context.set_theme(&theme) {
+        let why = Error::ConfigError(format!("Unknown theme '{}'.", theme));
+        errors.push(("Config Error", why));
+    }
+
+    let (scripts, script_errors) = Scripts::load();
+    errors.extend(script_errors.into_iter().map(|why| ("Script Error", why)));
+    context.keymap.bind_scripts(scripts.key_bindings());
+
+    // Logging in again is only needed without a stored
+    // session or when a different user was given
+    let session = match load_session() {
+        Ok(session) => session.filter(|session| {
+            let username = &options.credentials.username;
+            username.is_empty()
+                || AuthCreds::from_session(session)
+                    .username
+                    .eq_ignore_ascii_case(username)
+        }),
+        Err(why) => {
+            errors.push(("Session Error", why));
+            None
+        },
+    };
+
+    if let Err(why) = spawn_signal_listener(context.clone_sender()) {
+        errors.push(("Signal Error", why));
+    }
+
+    // Removes the socket when dropped at the end
+    let _control_socket = if context.config.app.control_socket {
+        match spawn_control_listener(context.clone_sender()) {
+            Ok(socket) => Some(socket),
+            Err(why) => {
+                errors.push(("Control Socket Error", why));
+                None
+            },
+        }
+    } else {
+        None
+    };
 
-    enable_raw_mode().expect("Unable to enable raw mode.");
+    for (title, why) in &errors {
+        warn!("{}: {}", title, why);
+    }
 
-    let mut out = stdout();
-    execute!(out, EnterAlternateScreen, EnableMouseCapture)
-        .expect("Unable to enter new screen.");
+    // Restores the terminal when this returns, even if it's
+    // through an error
+    let _terminal = TerminalGuard::new(!options.no_mouse)?;
 
-    let backend = CrosstermBackend::new(out);
+    let backend = CrosstermBackend::new(stdout());
     let mut term = Terminal::new(backend)?;
 
-    let mut app = App::new(context);
+    let mut app = App::new(context, scripts, options.credentials);
+    if let Some(session) = session {
+        info!("Restoring session for {}", session.session.user_id);
+        app.client_handle = Some(app.context.restore_client(session));
+    }
+    app.update_view();
+
+    let size = term.size()?;
+    app.on_resize(size.width, size.height);
+
+    show_startup_errors(&app.context, &errors);
 
-    term.clear().expect("Unable to clean terminal.");
+    term.clear()?;
 
-    let key_listen_timout = 100;
-    let event_rec = spawn_event_listener(key_listen_timout);
+    let event_rec = spawn_event_listener(tick_rate);
+    set_app_running(true);
 
     loop {
-        term.draw(|f| app.draw(f)).unwrap();
+        // Another thread panicked, the terminal has already
+        // been restored so only the client is left to stop
+        if has_panicked() {
+            break;
+        }
+
+        term.draw(|f| app.draw(f))?;
 
         handle_event(&event_rec.receiver, &mut app);
         handle_notification(&noti_rec, &mut app);
@@ -207,12 +1025,30 @@ pub fn start_app() -> Result<()> {
         if app.context.settings.quit_application {
             break;
         }
+
+        if app.context.settings.suspend {
+            app.context.settings.suspend = false;
+
+            info!("Suspending");
+            terminal::suspend()?;
+            info!("Resuming");
+
+            app.context.settings.redraw = true;
+        }
+
+        // The screen may have been changed or resized while
+        // the app was stopped
+        if app.context.settings.redraw {
+            app.context.settings.redraw = false;
+
+            term.clear()?;
+            let size = term.size()?;
+            app.on_resize(size.width, size.height);
+        }
     }
 
-    let mut out = stdout();
-    disable_raw_mode().expect("Unable to disable raw mode.");
-    execute!(out, LeaveAlternateScreen, DisableMouseCapture)
-        .expect("Unable to restore screen.");
+    app.shutdown();
 
+    info!("Exiting");
     Ok(())
 }
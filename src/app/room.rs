@@ -0,0 +1,43 @@
+use std::time::Instant;
+
+// Spaces aren't listed separately, the client doesn't
+// expose the room type from the create event so they show
+// as rooms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomKind {
+    Room,
+    Direct,
+    Invite,
+}
+
+impl RoomKind {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Room => "#",
+            Self::Direct => "@",
+            Self::Invite => "+",
+        }
+    }
+}
+
+// Summary of a room used for listing and searching rooms
+#[derive(Debug, Clone)]
+pub struct RoomEntry {
+    pub id:           String,
+    pub name:         String,
+    pub alias:        Option<String>,
+    pub kind:         RoomKind,
+    pub members:      Vec<String>,
+    pub unread:       u64,
+    // Newest event received this session, read receipts can
+    // only be sent once there is one
+    pub latest_event: Option<String>,
+    pub last_viewed:  Option<Instant>,
+}
+
+impl RoomEntry {
+    // Aliases should start with '#' and have a server name
+    pub fn is_alias(text: &str) -> bool {
+        text.starts_with('#') && text.contains(':') && !text.contains(' ')
+    }
+}
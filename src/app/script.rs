@@ -0,0 +1,513 @@
+use std::{
+    cell::RefCell,
+    ffi::OsStr,
+    fs::read_dir,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use rhai::{
+    module_resolvers::DummyModuleResolver, Dynamic, Engine, EvalAltResult,
+    FuncArgs, ImmutableString, Map, Scope, AST,
+};
+use tracing::{debug, info};
+
+use super::keymap::{Action, KeySequence};
+use crate::{
+    client::hooks::HookEvent,
+    config::HookTrigger,
+    error::{Error, Result},
+    fs::CONFIG_DIRECTORY,
+};
+
+const SCRIPT_DIRECTORY_NAME: &str = "scripts";
+// Stops scripts with infinite loops from freezing the app
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_COLLECTION_SIZE: usize = 1024;
+
+type RhaiResult = std::result::Result<(), Box<EvalAltResult>>;
+
+// Events which handlers can be registered for, keywords are
+// only used by hooks
+const SCRIPT_EVENTS: [HookTrigger; 4] = [
+    HookTrigger::Message,
+    HookTrigger::Mention,
+    HookTrigger::Direct,
+    HookTrigger::Invite,
+];
+
+// Requests made by a script, applied by the app once the
+// script returns
+#[derive(Debug, Clone)]
+pub enum ScriptEffect {
+    SendMessage {
+        room: String,
+        body: String,
+    },
+    Notify(String),
+    OpenRoom(String),
+    RunAction(Action),
+}
+
+// Effects and errors from every handler that was called
+#[derive(Debug, Default)]
+pub struct ScriptOutput {
+    pub effects: Vec<ScriptEffect>,
+    pub errors:  Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Handler {
+    script:   usize,
+    function: String,
+    // Number of arguments the handler is called with
+    params:   usize,
+}
+
+// Lengths of each list, used to undo the registrations of a
+// script which fails to load
+#[derive(Clone, Copy)]
+struct RegistrySnapshot {
+    commands:     usize,
+    events:       usize,
+    send_filters: usize,
+    key_bindings: usize,
+}
+
+// Filled in by the API functions while scripts are loaded
+// and called
+#[derive(Default)]
+struct Registry {
+    // Index of the script which is being loaded
+    loading:      usize,
+    commands:     Vec<(String, Handler)>,
+    events:       Vec<(HookTrigger, Handler)>,
+    send_filters: Vec<Handler>,
+    key_bindings: Vec<(KeySequence, Handler)>,
+    effects:      Vec<ScriptEffect>,
+}
+
+impl Registry {
+    fn handler(&self, function: &str, params: usize) -> Handler {
+        Handler {
+            script: self.loading,
+            function: function.to_string(),
+            params,
+        }
+    }
+
+    fn snapshot(&self) -> RegistrySnapshot {
+        RegistrySnapshot {
+            commands:     self.commands.len(),
+            events:       self.events.len(),
+            send_filters: self.send_filters.len(),
+            key_bindings: self.key_bindings.len(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: RegistrySnapshot) {
+        self.commands.truncate(snapshot.commands);
+        self.events.truncate(snapshot.events);
+        self.send_filters.truncate(snapshot.send_filters);
+        self.key_bindings.truncate(snapshot.key_bindings);
+        self.effects.clear();
+    }
+
+    fn handlers(&self) -> impl Iterator<Item = &Handler> {
+        self.commands
+            .iter()
+            .map(|(_, handler)| handler)
+            .chain(self.events.iter().map(|(_, handler)| handler))
+            .chain(self.send_filters.iter())
+            .chain(self.key_bindings.iter().map(|(_, handler)| handler))
+    }
+}
+
+struct Script {
+    name: String,
+    // Only the functions are kept so that calling a
+    // handler doesn't register everything again
+    ast:  AST,
+}
+
+pub struct Scripts {
+    engine:   Engine,
+    scripts:  Vec<Script>,
+    registry: Rc<RefCell<Registry>>,
+}
+
+impl Scripts {
+    // No scripts are loaded
+    pub fn new() -> Self {
+        let registry = Rc::new(RefCell::new(Registry::default()));
+
+        Self {
+            engine: create_engine(&registry),
+            scripts: Vec::new(),
+            registry,
+        }
+    }
+
+    // Loads every script in the scripts directory next to
+    // the config, scripts which fail to load are skipped
+    pub fn load() -> (Self, Vec<Error>) {
+        let mut this = Self::new();
+        let mut errors = Vec::new();
+
+        let dir = match CONFIG_DIRECTORY.as_ref() {
+            Ok(dir) => dir.join(SCRIPT_DIRECTORY_NAME),
+            Err(_) => return (this, errors),
+        };
+
+        let mut paths = match read_dir(&dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension() == Some(OsStr::new("rhai")))
+                .collect::<Vec<PathBuf>>(),
+            Err(_) => return (this, errors),
+        };
+        // Scripts are loaded in a consistent order so the
+        // first to register a command or key wins
+        paths.sort();
+
+        for path in paths {
+            if let Err(why) = this.load_script(&path) {
+                errors.push(why);
+            }
+        }
+
+        (this, errors)
+    }
+
+    fn load_script(&mut self, path: &Path) -> Result<()> {
+        let name = path.file_stem().map_or_else(String::new, |stem| {
+            stem.to_string_lossy().to_string()
+        });
+        let script_error = |why: Box<EvalAltResult>| {
+            Error::ConfigError(format!(
+                "Unable to load script '{}'.\n{}",
+                path.display(),
+                why
+            ))
+        };
+
+        let ast = self
+            .engine
+            .compile_file(path.to_path_buf())
+            .map_err(script_error)?;
+
+        // Registrations are only kept if the whole script runs
+        let registry = self.registry.borrow().snapshot();
+        self.registry.borrow_mut().loading = self.scripts.len();
+        if let Err(why) =
+            self.engine.run_ast_with_scope(&mut Scope::new(), &ast)
+        {
+            self.registry.borrow_mut().restore(registry);
+            return Err(script_error(why));
+        }
+
+        // Handlers are checked here as they're registered by
+        // name
+        let missing = self
+            .registry
+            .borrow()
+            .handlers()
+            .filter(|handler| handler.script == self.scripts.len())
+            .find(|handler| {
+                !ast.iter_functions().any(|meta| {
+                    meta.name == handler.function
+                        && meta.params.len() == handler.params
+                })
+            })
+            .cloned();
+        if let Some(handler) = missing {
+            self.registry.borrow_mut().restore(registry);
+            return Err(Error::ConfigError(format!(
+                "Unable to load script '{}'.\nFunction '{}' taking {} \
+                 argument(s) isn't defined.",
+                path.display(),
+                handler.function,
+                handler.params
+            )));
+        }
+
+        // Anything requested while loading is dropped as
+        // there's no app to apply it to yet
+        self.registry.borrow_mut().effects.clear();
+
+        info!("Loaded script '{}'", name);
+        self.scripts.push(Script {
+            name,
+            ast: ast.clone_functions_only(),
+        });
+
+        Ok(())
+    }
+
+    // Pairs of keys and a description, the index is passed
+    // to `run_key_binding` when the keys are pressed
+    pub fn key_bindings(&self) -> Vec<(KeySequence, String)> {
+        self.registry
+            .borrow()
+            .key_bindings
+            .iter()
+            .map(|(keys, handler)| (keys.clone(), self.describe(handler)))
+            .collect()
+    }
+
+    pub fn run_key_binding(&self, index: usize) -> ScriptOutput {
+        let handler = self
+            .registry
+            .borrow()
+            .key_bindings
+            .get(index)
+            .map(|(_, handler)| handler.clone());
+
+        let mut output = ScriptOutput::default();
+        if let Some(handler) = handler {
+            self.call(&handler, (), &mut output);
+        }
+        self.take_effects(output)
+    }
+
+    // Returns nothing if no script registered the command
+    pub fn run_command(&self, name: &str, args: &str) -> Option<ScriptOutput> {
+        let handler = self
+            .registry
+            .borrow()
+            .commands
+            .iter()
+            .find(|(command, _)| command == name)
+            .map(|(_, handler)| handler.clone())?;
+
+        let mut output = ScriptOutput::default();
+        self.call(&handler, (args.to_string(),), &mut output);
+        Some(self.take_effects(output))
+    }
+
+    pub fn on_event(&self, event: &HookEvent) -> ScriptOutput {
+        let handlers = self
+            .registry
+            .borrow()
+            .events
+            .iter()
+            .filter(|(trigger, _)| event.triggers.contains(trigger))
+            .map(|(_, handler)| handler.clone())
+            .collect::<Vec<Handler>>();
+
+        let mut output = ScriptOutput::default();
+        for handler in handlers {
+            self.call(&handler, (event_map(event),), &mut output);
+        }
+        self.take_effects(output)
+    }
+
+    // Passes the message through each filter in turn, a
+    // filter returning anything other than a string cancels
+    // sending the message
+    pub fn filter_outgoing(
+        &self,
+        room: &str,
+        body: String,
+    ) -> (Option<String>, ScriptOutput) {
+        let handlers = self.registry.borrow().send_filters.clone();
+
+        let mut output = ScriptOutput::default();
+        let mut body = Some(body);
+        for handler in handlers {
+            let current = match body.take() {
+                Some(current) => current,
+                None => break,
+            };
+
+            let args = (room.to_string(), current.clone());
+            body = match self.call(&handler, args, &mut output) {
+                Some(result) => result
+                    .try_cast::<ImmutableString>()
+                    .map(|text| text.to_string()),
+                // Errors shouldn't stop the message being sent
+                None => Some(current),
+            };
+        }
+
+        (body, self.take_effects(output))
+    }
+
+    fn call(
+        &self,
+        handler: &Handler,
+        args: impl FuncArgs,
+        output: &mut ScriptOutput,
+    ) -> Option<Dynamic> {
+        let script = self.scripts.get(handler.script)?;
+        debug!("Calling {}", self.describe(handler));
+
+        let result = self.engine.call_fn::<Dynamic>(
+            &mut Scope::new(),
+            &script.ast,
+            &handler.function,
+            args,
+        );
+        match result {
+            Ok(result) => Some(result),
+            Err(why) => {
+                output
+                    .errors
+                    .push(format!("Script '{}' failed.\n{}", script.name, why));
+                None
+            },
+        }
+    }
+
+    fn take_effects(&self, mut output: ScriptOutput) -> ScriptOutput {
+        output.effects =
+            std::mem::take(&mut self.registry.borrow_mut().effects);
+        output
+    }
+
+    fn describe(&self, handler: &Handler) -> String {
+        let script = self
+            .scripts
+            .get(handler.script)
+            .map_or("", |script| script.name.as_str());
+
+        format!("{}::{}", script, handler.function)
+    }
+}
+
+fn event_map(event: &HookEvent) -> Map {
+    let triggers = event
+        .triggers
+        .iter()
+        .map(|trigger| Dynamic::from(trigger.name().to_string()))
+        .collect::<Vec<Dynamic>>();
+    let alias = event
+        .room_alias
+        .clone()
+        .map_or(Dynamic::UNIT, Dynamic::from);
+
+    let mut map = Map::new();
+    map.insert("triggers".into(), Dynamic::from(triggers));
+    map.insert("room_id".into(), Dynamic::from(event.room_id.clone()));
+    map.insert("room_name".into(), Dynamic::from(event.room_name.clone()));
+    map.insert("room_alias".into(), alias);
+    map.insert("sender".into(), Dynamic::from(event.sender.clone()));
+    map.insert("body".into(), Dynamic::from(event.body.clone()));
+
+    map
+}
+
+// Scripts can't import other files or use eval and are
+// limited in how much work they can do per call
+fn create_engine(registry: &Rc<RefCell<Registry>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .disable_symbol("eval");
+
+    // Printing would draw over the app
+    engine.on_print(|text| info!(target: "konoha::script", "{}", text));
+    engine.on_debug(|text, source, position| {
+        debug!(
+            target: "konoha::script",
+            "{} ({}:{})",
+            text,
+            source.unwrap_or(""),
+            position
+        )
+    });
+
+    register_api(&mut engine, registry);
+    engine
+}
+
+fn register_api(engine: &mut Engine, registry: &Rc<RefCell<Registry>>) {
+    // Commands are run from the command line as `:name`
+    let reg = registry.clone();
+    engine.register_fn(
+        "register_command",
+        move |name: &str, function: &str| {
+            let mut registry = reg.borrow_mut();
+            let handler = registry.handler(function, 1);
+            let name = name.trim_start_matches([':', '/']);
+            registry.commands.push((name.to_string(), handler));
+        },
+    );
+
+    let reg = registry.clone();
+    engine.register_fn(
+        "on_event",
+        move |event: &str, function: &str| -> RhaiResult {
+            let trigger = SCRIPT_EVENTS
+                .iter()
+                .find(|trigger| trigger.name() == event)
+                .copied()
+                .ok_or_else(|| format!("unknown event '{}'", event))?;
+
+            let mut registry = reg.borrow_mut();
+            let handler = registry.handler(function, 1);
+            registry.events.push((trigger, handler));
+            Ok(())
+        },
+    );
+
+    let reg = registry.clone();
+    engine.register_fn("on_send", move |function: &str| {
+        let mut registry = reg.borrow_mut();
+        let handler = registry.handler(function, 2);
+        registry.send_filters.push(handler);
+    });
+
+    let reg = registry.clone();
+    engine.register_fn(
+        "bind_key",
+        move |keys: &str, function: &str| -> RhaiResult {
+            let keys = keys.parse::<KeySequence>()?;
+
+            let mut registry = reg.borrow_mut();
+            let handler = registry.handler(function, 0);
+            registry.key_bindings.push((keys, handler));
+            Ok(())
+        },
+    );
+
+    let reg = registry.clone();
+    engine.register_fn("send_message", move |room: &str, body: &str| {
+        reg.borrow_mut().effects.push(ScriptEffect::SendMessage {
+            room: room.to_string(),
+            body: body.to_string(),
+        });
+    });
+
+    let reg = registry.clone();
+    engine.register_fn("notify", move |text: &str| {
+        reg.borrow_mut()
+            .effects
+            .push(ScriptEffect::Notify(text.to_string()));
+    });
+
+    let reg = registry.clone();
+    engine.register_fn("open_room", move |room: &str| {
+        reg.borrow_mut()
+            .effects
+            .push(ScriptEffect::OpenRoom(room.to_string()));
+    });
+
+    let reg = registry.clone();
+    engine.register_fn("run_action", move |name: &str| -> RhaiResult {
+        let action = name.parse::<Action>()?;
+        reg.borrow_mut()
+            .effects
+            .push(ScriptEffect::RunAction(action));
+        Ok(())
+    });
+}
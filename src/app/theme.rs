@@ -0,0 +1,467 @@
+use std::{
+    ffi::OsStr,
+    fs::{read_dir, read_to_string},
+    path::Path,
+    result::Result as StdResult,
+};
+
+use serde::{Deserialize, Deserializer};
+use tracing::debug;
+use tui::{
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders},
+};
+
+use crate::{
+    error::{Error, Result},
+    fs::CONFIG_DIRECTORY,
+};
+
+pub const THEME_DIRECTORY_NAME: &str = "themes";
+pub const DEFAULT_THEME: &str = "dark";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    // Unnamed themes are named after their file
+    #[serde(default)]
+    pub name: String,
+    pub palette: Palette,
+    #[serde(deserialize_with = "deserialize_border_type")]
+    pub border_type: BorderType,
+    #[serde(deserialize_with = "deserialize_border_type")]
+    pub popup_border_type: BorderType,
+    pub focus: ThemeStyle,
+    pub selection: ThemeStyle,
+    #[serde(deserialize_with = "deserialize_colors")]
+    pub sender_colors: Vec<Color>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Palette {
+    #[serde(deserialize_with = "deserialize_color")]
+    pub foreground: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub background: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub accent:     Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub muted:      Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub error:      Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub warning:    Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub success:    Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub border:     Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Theme::dark().palette
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeStyle {
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub fg:        Option<Color>,
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub bg:        Option<Color>,
+    #[serde(deserialize_with = "deserialize_modifier")]
+    pub modifiers: Modifier,
+}
+
+impl Default for ThemeStyle {
+    fn default() -> Self {
+        Self::new(None, None, Modifier::empty())
+    }
+}
+
+impl ThemeStyle {
+    pub fn new(
+        fg: Option<Color>,
+        bg: Option<Color>,
+        modifiers: Modifier,
+    ) -> Self {
+        Self {
+            fg,
+            bg,
+            modifiers,
+        }
+    }
+
+    pub fn to_style(&self) -> Style {
+        let mut style = Style::default().add_modifier(self.modifiers);
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
+        }
+
+        style
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            palette: Palette {
+                foreground: Color::Reset,
+                background: Color::Reset,
+                accent:     Color::Cyan,
+                muted:      Color::Indexed(8),
+                error:      Color::Red,
+                warning:    Color::Yellow,
+                success:    Color::Green,
+                border:     Color::Reset,
+            },
+            border_type: BorderType::Plain,
+            popup_border_type: BorderType::Rounded,
+            focus: ThemeStyle::new(None, None, Modifier::BOLD),
+            selection: ThemeStyle::new(
+                None,
+                Some(Color::Indexed(237)),
+                Modifier::BOLD,
+            ),
+            sender_colors: [33, 39, 70, 136, 166, 168, 171, 178]
+                .iter()
+                .map(|idx| Color::Indexed(*idx))
+                .collect(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            palette: Palette {
+                foreground: Color::Black,
+                background: Color::White,
+                accent:     Color::Blue,
+                muted:      Color::Gray,
+                error:      Color::Red,
+                warning:    Color::Indexed(130),
+                success:    Color::Green,
+                border:     Color::Black,
+            },
+            border_type: BorderType::Plain,
+            popup_border_type: BorderType::Rounded,
+            focus: ThemeStyle::new(None, None, Modifier::BOLD),
+            selection: ThemeStyle::new(
+                None,
+                Some(Color::Indexed(253)),
+                Modifier::BOLD,
+            ),
+            sender_colors: [19, 22, 52, 54, 88, 94, 130, 161]
+                .iter()
+                .map(|idx| Color::Indexed(*idx))
+                .collect(),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            palette: Palette {
+                foreground: Color::White,
+                background: Color::Black,
+                accent:     Color::Yellow,
+                muted:      Color::White,
+                error:      Color::LightRed,
+                warning:    Color::LightYellow,
+                success:    Color::LightGreen,
+                border:     Color::White,
+            },
+            border_type: BorderType::Thick,
+            popup_border_type: BorderType::Double,
+            focus: ThemeStyle::new(
+                None,
+                None,
+                Modifier::BOLD | Modifier::REVERSED,
+            ),
+            selection: ThemeStyle::new(
+                Some(Color::Black),
+                Some(Color::Yellow),
+                Modifier::BOLD,
+            ),
+            sender_colors: vec![
+                Color::LightYellow,
+                Color::LightCyan,
+                Color::LightGreen,
+                Color::LightMagenta,
+            ],
+        }
+    }
+
+    // Only uses the 16 basic colors for terminals without
+    // 256 color support
+    pub fn basic() -> Self {
+        Self {
+            name: "16-color".to_string(),
+            palette: Palette {
+                foreground: Color::Reset,
+                background: Color::Reset,
+                accent:     Color::Cyan,
+                muted:      Color::DarkGray,
+                error:      Color::Red,
+                warning:    Color::Yellow,
+                success:    Color::Green,
+                border:     Color::Reset,
+            },
+            border_type: BorderType::Plain,
+            popup_border_type: BorderType::Plain,
+            focus: ThemeStyle::new(None, None, Modifier::BOLD),
+            selection: ThemeStyle::new(None, None, Modifier::REVERSED),
+            sender_colors: vec![
+                Color::Red,
+                Color::Green,
+                Color::Yellow,
+                Color::Blue,
+                Color::Magenta,
+                Color::Cyan,
+            ],
+        }
+    }
+
+    pub fn base_style(&self) -> Style {
+        Style::default()
+            .fg(self.palette.foreground)
+            .bg(self.palette.background)
+    }
+
+    pub fn block(&self) -> Block<'static> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(self.border_type)
+            .border_style(Style::default().fg(self.palette.border))
+    }
+
+    pub fn popup_block(&self) -> Block<'static> {
+        self.block().border_type(self.popup_border_type)
+    }
+}
+
+// Returns the built in themes followed by any themes found
+// in the theme directory. Themes which fail to load are
+// returned as errors so they can be shown to the user
+pub fn load_themes() -> (Vec<Theme>, Vec<Error>) {
+    let mut themes = vec![
+        Theme::dark(),
+        Theme::light(),
+        Theme::high_contrast(),
+        Theme::basic(),
+    ];
+    let mut errors = Vec::new();
+
+    let dir = match CONFIG_DIRECTORY.as_ref() {
+        Ok(dir) => dir.join(THEME_DIRECTORY_NAME),
+        Err(_) => return (themes, errors),
+    };
+
+    let entries = match read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return (themes, errors),
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension() != Some(OsStr::new("toml")) {
+            continue;
+        }
+
+        match load_theme(&path) {
+            Ok(theme) => {
+                debug!("Loaded theme '{}'", theme.name);
+                // Allow overriding the built in themes
+                themes.retain(|other| other.name != theme.name);
+                themes.push(theme);
+            },
+            Err(why) => errors.push(why),
+        }
+    }
+
+    (themes, errors)
+}
+
+fn load_theme(path: &Path) -> Result<Theme> {
+    let text = read_to_string(path)?;
+    let mut theme = toml::from_str::<Theme>(&text).map_err(|why| {
+        Error::ConfigError(format!(
+            "Unable to parse theme '{}'.\n{}",
+            path.display(),
+            why
+        ))
+    })?;
+
+    if theme.name.is_empty() {
+        if let Some(stem) = path.file_stem() {
+            theme.name = stem.to_string_lossy().to_string();
+        }
+    }
+
+    Ok(theme)
+}
+
+pub fn parse_color(text: &str) -> StdResult<Color, String> {
+    let color = match text.to_lowercase().replace('-', "_").as_ref() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "dark_gray" | "dark_grey" => Color::DarkGray,
+        "light_red" => Color::LightRed,
+        "light_green" => Color::LightGreen,
+        "light_yellow" => Color::LightYellow,
+        "light_blue" => Color::LightBlue,
+        "light_magenta" => Color::LightMagenta,
+        "light_cyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') => {
+            // Checked up front so slicing can't split a multi-byte
+            // character and signs aren't accepted as digits
+            let digits = &hex[1..];
+            if digits.len() != 6
+                || !digits.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                return Err(format!("invalid hex color '{}'", text));
+            }
+
+            let channel = |idx: usize| {
+                u8::from_str_radix(&digits[idx..idx + 2], 16)
+                    .map_err(|_| format!("invalid hex color '{}'", text))
+            };
+
+            Color::Rgb(channel(0)?, channel(2)?, channel(4)?)
+        },
+        index => index.parse::<u8>().map(Color::Indexed).map_err(|_| {
+            format!(
+                "unknown color '{}', expected a name, '#rrggbb' or 0-255",
+                text
+            )
+        })?,
+    };
+
+    Ok(color)
+}
+
+fn parse_modifier(text: &str) -> StdResult<Modifier, String> {
+    let modifier = match text.to_lowercase().as_ref() {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underlined" => Modifier::UNDERLINED,
+        "slow_blink" => Modifier::SLOW_BLINK,
+        "rapid_blink" => Modifier::RAPID_BLINK,
+        "reversed" => Modifier::REVERSED,
+        "hidden" => Modifier::HIDDEN,
+        "crossed_out" => Modifier::CROSSED_OUT,
+        _ => return Err(format!("unknown modifier '{}'", text)),
+    };
+
+    Ok(modifier)
+}
+
+fn deserialize_color<'de, D>(deserializer: D) -> StdResult<Color, D::Error>
+where
+    D: Deserializer<'de>, {
+    let text = String::deserialize(deserializer)?;
+    parse_color(&text).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_optional_color<'de, D>(
+    deserializer: D,
+) -> StdResult<Option<Color>, D::Error>
+where
+    D: Deserializer<'de>, {
+    deserialize_color(deserializer).map(Some)
+}
+
+fn deserialize_colors<'de, D>(
+    deserializer: D,
+) -> StdResult<Vec<Color>, D::Error>
+where
+    D: Deserializer<'de>, {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|text| parse_color(text).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn deserialize_modifier<'de, D>(
+    deserializer: D,
+) -> StdResult<Modifier, D::Error>
+where
+    D: Deserializer<'de>, {
+    Vec::<String>::deserialize(deserializer)?.iter().try_fold(
+        Modifier::empty(),
+        |modifiers, text| {
+            parse_modifier(text)
+                .map(|modifier| modifiers | modifier)
+                .map_err(serde::de::Error::custom)
+        },
+    )
+}
+
+fn deserialize_border_type<'de, D>(
+    deserializer: D,
+) -> StdResult<BorderType, D::Error>
+where
+    D: Deserializer<'de>, {
+    let text = String::deserialize(deserializer)?;
+    match text.to_lowercase().as_ref() {
+        "plain" => Ok(BorderType::Plain),
+        "rounded" => Ok(BorderType::Rounded),
+        "double" => Ok(BorderType::Double),
+        "thick" => Ok(BorderType::Thick),
+        _ => Err(serde::de::Error::custom(format!(
+            "unknown border type '{}', expected plain, rounded, double or \
+             thick",
+            text
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::style::Color;
+
+    use super::parse_color;
+
+    #[test]
+    fn parses_names_and_indexes() {
+        assert_eq!(parse_color("Light-Blue"), Ok(Color::LightBlue));
+        assert_eq!(parse_color("dark_grey"), Ok(Color::DarkGray));
+        assert_eq!(parse_color("42"), Ok(Color::Indexed(42)));
+        assert!(parse_color("256").is_err());
+        assert!(parse_color("purple").is_err());
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_color("#ff8000"), Ok(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("#FF8000"), Ok(Color::Rgb(255, 128, 0)));
+    }
+
+    #[test]
+    fn rejects_invalid_hex_colors() {
+        for text in &["#fff", "#ff80000", "#gg0000", "#+f+f+f", "#a\u{e9}bbb"] {
+            assert!(parse_color(text).is_err(), "{} was accepted", text);
+        }
+    }
+}
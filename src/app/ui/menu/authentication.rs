@@ -1,20 +1,19 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use lazy_static::lazy_static;
 use regex::Regex;
-use tui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    widgets::{Block, Borders},
-};
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 
 use super::Menu;
 use crate::{
     app::{
         context::{Context, Notification},
         event::Event,
-        helper::{self, split_rect, CenterPosition, CrosstermFrame},
+        helper::{
+            self, split_rect, Breakpoint, CenterPosition, CrosstermFrame,
+        },
+        keymap::Action,
         ui::prelude::{
-            message::PopupMessageBuilder, ButtonWidget, LabeledInputWidget,
-            ValidationType, Widget,
+            message::PopupMessageBuilder, ButtonWidget, Form, FormResult,
+            LabeledInputWidget, ValidationType,
         },
     },
     client::auth::AuthCreds,
@@ -28,64 +27,35 @@ lazy_static! {
     .unwrap();
 }
 
-#[derive(Clone)]
 pub struct AuthenticateMenu {
-    focus_index: u8,
-    username:    LabeledInputWidget,
-    password:    LabeledInputWidget,
-    submit:      ButtonWidget,
+    form: Form,
 }
 
 impl Default for AuthenticateMenu {
     fn default() -> Self {
-        let username = LabeledInputWidget::new("Username")
-            .set_selected(true)
-            .set_validation(ValidationType::Functional(|username| {
-                USERNAME_REGEX.is_match(&username)
-            }))
-            .to_owned();
-
-        let password = LabeledInputWidget::new("Password")
-            .set_secret(true)
-            .set_validation(ValidationType::Functional(|password| {
-                !password.is_empty()
-            }))
-            .to_owned();
-
-        let submit = ButtonWidget::new("Login", |_| {});
-
-        Self {
-            focus_index: 0,
-            username,
-            password,
-            submit,
-        }
+        Self::new(AuthCreds::default())
     }
 }
 
 impl Menu for AuthenticateMenu {
     fn on_event(&mut self, event: Event, ctx: &Context) {
         match event {
-            Event::Tick => self.on_tick(ctx),
-            Event::Key(key) => self.handle_key(key, ctx),
+            Event::Tick => self.form.on_tick(ctx),
+            Event::Key(key) => self.form.on_key(ctx, key),
+            Event::Action(action) => {
+                let result = self.form.on_action(ctx, action);
+                self.handle_result(result, ctx);
+            },
+            Event::Mouse(event) => {
+                let result = self.form.on_mouse(ctx, event);
+                self.handle_result(result, ctx);
+            },
             _ => {},
         }
     }
 
-    fn get_help_message(
-        &mut self,
-        _ctx: &Context,
-    ) -> Vec<(KeyModifiers, KeyCode, String)> {
-        vec![
-            (KeyModifiers::NONE, KeyCode::Up, "Select up".to_string()),
-            (KeyModifiers::NONE, KeyCode::Down, "Select down".to_string()),
-            (
-                KeyModifiers::NONE,
-                KeyCode::Enter,
-                "Submit login".to_string(),
-            ),
-            (KeyModifiers::NONE, KeyCode::Tab, "Next field".to_string()),
-        ]
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
+        self.form.get_actions()
     }
 
     fn draw(
@@ -94,7 +64,8 @@ impl Menu for AuthenticateMenu {
         max_size: Rect,
         ctx: &Context,
     ) {
-        if max_size.width >= 42 {
+        if ctx.settings.breakpoint != Breakpoint::Narrow && max_size.width >= 42
+        {
             // If help menu is shown, lower the max
             // size by 3 so that it
             // doesn't move when toggling the menu
@@ -118,92 +89,93 @@ impl Menu for AuthenticateMenu {
                 helper::centered_line(36, 1, 3, size),
             )[1];
 
-            self.username
-                .render(helper::centered_line(36, 1, 1, size), frame);
-            self.password
-                .render(helper::centered_line(36, 1, 2, size), frame);
-            self.submit.render(button_chunk, frame);
+            self.form.render(
+                &[
+                    helper::centered_line(36, 1, 1, size),
+                    helper::centered_line(36, 1, 2, size),
+                    button_chunk,
+                ],
+                frame,
+                ctx,
+            );
 
-            let frame_block = Block::default()
-                .title("Login to Matrix")
-                .borders(Borders::ALL);
+            let frame_block = ctx.theme.block().title("Login to Matrix");
             frame.render_widget(frame_block, size);
         } else {
+            // Drop the border and use the full width so that
+            // the form still fits in narrow terminals
+            let size = helper::centered_rect(
+                CenterPosition::AbsoluteInner(max_size.width, 3),
+                max_size,
+            );
+
+            let lines = (0..self.form.len() as u16)
+                .map(|idx| helper::centered_line(size.width, 1, idx, size))
+                .collect::<Vec<Rect>>();
+            self.form.render(&lines, frame, ctx);
         }
     }
 
     fn get_minimum_size(&mut self) -> (u16, u16) {
-        (42, 6)
+        (20, 3)
     }
 }
 
 impl AuthenticateMenu {
     pub fn new(credentials: AuthCreds) -> Self {
-        let mut default = Self::default();
-        default.username.input.set_value(format!(
-            "@{}:{}",
-            credentials.username, credentials.homeserver
-        ));
-        default.password.input.set_value(credentials.password);
-
-        default
-    }
-
-    fn on_tick(&mut self, ctx: &Context) {
-        self.username.on_tick(ctx);
-        self.password.on_tick(ctx);
-        self.submit.on_tick(ctx);
-    }
+        let mut username = LabeledInputWidget::new("Username")
+            .set_validation(ValidationType::Functional(|username| {
+                USERNAME_REGEX.is_match(&username)
+            }))
+            .to_owned();
 
-    fn handle_key(&mut self, key: KeyEvent, ctx: &Context) {
-        match key.code {
-            KeyCode::Up | KeyCode::BackTab => {
-                if self.focus_index == 0 {
-                    self.focus_index = 2;
-                } else {
-                    self.focus_index -= 1;
-                }
+        let mut password = LabeledInputWidget::new("Password")
+            .set_secret(true)
+            .set_validation(ValidationType::Functional(|password| {
+                !password.is_empty()
+            }))
+            .to_owned();
 
-                self.username.set_selected(self.focus_index == 0);
-                self.password.set_selected(self.focus_index == 1);
-                self.submit.set_selected(self.focus_index == 2);
+        // Either may be given on the command line without the
+        // other
+        if !credentials.username.is_empty()
+            || !credentials.homeserver.is_empty()
+        {
+            username.input.set_value(format!(
+                "@{}:{}",
+                credentials.username, credentials.homeserver
+            ));
+            password.input.set_value(credentials.password);
+        }
 
-                return;
-            },
-            KeyCode::Down | KeyCode::Tab => {
-                self.focus_index += 1;
-                self.focus_index %= 3;
+        let mut form = Form::default();
+        form.add_field("username", username)
+            .add_field("password", password)
+            .add_field("submit", ButtonWidget::new("Login", |_| {}))
+            .set_error("username", "Username should match '@user:domain'.")
+            .set_error("password", "No password specified.")
+            .set_submit("submit", true);
 
-                self.username.set_selected(self.focus_index == 0);
-                self.password.set_selected(self.focus_index == 1);
-                self.submit.set_selected(self.focus_index == 2);
+        Self {
+            form,
+        }
+    }
 
-                return;
+    fn handle_result(&mut self, result: FormResult, ctx: &Context) {
+        match result {
+            FormResult::Invalid(errors) => {
+                let mut popup_builder =
+                    PopupMessageBuilder::new(errors.join("\n"));
+                let popup = popup_builder
+                    .set_title(Some("Invalid Credentials"))
+                    .set_message_align(Alignment::Center)
+                    .to_popup();
+                ctx.send_notification(Notification::ShowPopup(popup));
             },
-            KeyCode::Enter => {
-                let error = if !self.username.input.is_valid() {
-                    Some("Username should match '@user:domain'.")
-                } else if !self.password.input.is_valid() {
-                    Some("No password specified.")
-                } else {
-                    None
-                };
-
-                if let Some(msg) = error {
-                    let mut popup_builder = PopupMessageBuilder::new(msg);
-                    let popup = popup_builder
-                        .set_title(Some("Invalid Credentials"))
-                        .set_message_align(Alignment::Center)
-                        .to_popup();
-                    // TODO: Logging
-                    let _ =
-                        ctx.send_notification(Notification::ShowPopup(popup));
-
-                    return;
-                }
-
+            FormResult::Submit => {
+                let username = self.form.get_value("username").unwrap();
                 let capture = USERNAME_REGEX
-                    .captures(&self.username.input.value)
+                    .captures(&username)
                     .expect("Couldn't capture username regex.");
 
                 let un_group = capture.name("un").unwrap();
@@ -215,17 +187,12 @@ impl AuthenticateMenu {
                 let credentials = AuthCreds {
                     username,
                     homeserver,
-                    password: self.password.input.value.clone(),
+                    password: self.form.get_value("password").unwrap(),
                 };
 
-                // TODO: Logging
-                let _ =
-                    ctx.send_notification(Notification::SetLogin(credentials));
+                ctx.send_notification(Notification::SetLogin(credentials));
             },
-            _ => {},
+            FormResult::None => {},
         }
-
-        self.username.on_key(ctx, key);
-        self.password.on_key(ctx, key);
     }
 }
@@ -1,6 +1,6 @@
-use crossterm::event::{KeyCode, KeyModifiers};
 use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Style,
     widgets::Paragraph,
 };
 
@@ -8,6 +8,7 @@ use super::{Event, Menu};
 use crate::app::{
     context::Context,
     helper::{centered_rect, CenterPosition, CrosstermFrame},
+    keymap::Action,
 };
 
 #[derive(Debug, Clone)]
@@ -28,10 +29,7 @@ impl Menu for LoadingMenu {
         }
     }
 
-    fn get_help_message(
-        &mut self,
-        _ctx: &Context,
-    ) -> Vec<(KeyModifiers, KeyCode, String)> {
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
         vec![]
     }
 
@@ -39,7 +37,7 @@ impl Menu for LoadingMenu {
         &mut self,
         frame: &mut CrosstermFrame,
         max_size: Rect,
-        _ctx: &Context,
+        ctx: &Context,
     ) {
         let width = (BAR_LENGTH + 2).max(self.text.len() as u16);
         let text_height = self.text.split('\n').count() as u16;
@@ -66,14 +64,15 @@ impl Menu for LoadingMenu {
             let len = BAR_LENGTH as usize;
             let tick = self.progress as usize;
             if tick <= len {
-                "█".repeat(tick) + &" ".repeat(len - tick)
+                "█".repeat(tick) + " ".repeat(len - tick).as_str()
             } else {
                 let tick = tick - len;
-                " ".repeat(tick) + &"█".repeat(len - tick)
+                " ".repeat(tick) + "█".repeat(len - tick).as_str()
             }
         };
-        let progress_bar =
-            Paragraph::new(progress_bar_text).alignment(Alignment::Center);
+        let progress_bar = Paragraph::new(progress_bar_text)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(ctx.theme.palette.accent));
         frame.render_widget(progress_bar, chunks[2]);
     }
 
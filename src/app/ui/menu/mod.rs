@@ -1,10 +1,9 @@
-use std::ops::DerefMut;
+use std::ops::{Deref, DerefMut};
 
-use crossterm::event::{KeyCode, KeyModifiers};
 use tui::layout::Rect;
 
 use super::super::{context::Context, helper::CrosstermFrame};
-use crate::app::event::Event;
+use crate::app::{event::Event, keymap::Action};
 
 pub mod authentication;
 pub mod loading;
@@ -19,12 +18,18 @@ pub trait Menu {
         ctx: &Context,
     );
 
-    fn get_help_message(
-        &mut self,
-        ctx: &Context,
-    ) -> Vec<(KeyModifiers, KeyCode, String)>;
+    // Actions which the menu currently responds to, these
+    // are also used to build the help bar
+    fn get_actions(&mut self, ctx: &Context) -> Vec<Action>;
 
     fn get_minimum_size(&mut self) -> (u16, u16);
+
+    // Name of the menu without the module path, this is
+    // included in crash reports
+    fn get_name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
 }
 
 impl Menu for Box<dyn Menu> {
@@ -41,14 +46,15 @@ impl Menu for Box<dyn Menu> {
         self.deref_mut().draw(frame, max_size, ctx)
     }
 
-    fn get_help_message(
-        &mut self,
-        ctx: &Context,
-    ) -> Vec<(KeyModifiers, KeyCode, String)> {
-        self.deref_mut().get_help_message(ctx)
+    fn get_actions(&mut self, ctx: &Context) -> Vec<Action> {
+        self.deref_mut().get_actions(ctx)
     }
 
     fn get_minimum_size(&mut self) -> (u16, u16) {
         self.deref_mut().get_minimum_size()
     }
+
+    fn get_name(&self) -> &'static str {
+        self.deref().get_name()
+    }
 }
@@ -1,4 +1,4 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 use lazy_static::lazy_static;
 use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -6,11 +6,12 @@ use tui::{
     widgets::{Paragraph, Wrap},
 };
 
-use super::{Popup, PopupArea, PopupPosition};
+use super::{Popup, PopupArea, PopupId, PopupPosition};
 use crate::app::{
     context::{Context, Notification},
     event::Event,
-    helper::{shrink_area, split_rect, CrosstermFrame, Spacing},
+    helper::{area_contains, shrink_area, split_rect, CrosstermFrame, Spacing},
+    keymap::Action,
     ui::prelude::{ButtonWidget, Menu, Widget},
 };
 
@@ -20,10 +21,12 @@ lazy_static! {
     static ref MESSAGE_SPACING: Spacing = Spacing::new(1, 1, 4, 4);
 }
 
-pub fn new_confirm_popup<T: ToString>(
-    message: T,
-    callback: fn(&Context),
-) -> Popup {
+pub type ConfirmCallback = Box<dyn FnOnce(&Context) + Send>;
+
+pub fn new_confirm_popup<T, F>(message: T, callback: F) -> Popup
+where
+    T: ToString,
+    F: FnOnce(&Context) + Send + 'static, {
     let area = {
         let (message_width, message_height) =
             format_padding(&message.to_string(), TITLE_SPACING.to_owned());
@@ -37,37 +40,48 @@ pub fn new_confirm_popup<T: ToString>(
         PopupArea::Absolute(width, height, PopupPosition::Center)
     };
 
+    let id = PopupId::next();
     Popup {
-        menu: Box::new(ConfirmMenu::new(message.to_string(), callback)),
+        id,
+        menu: Box::new(ConfirmMenu::new(
+            id,
+            message.to_string(),
+            Box::new(callback),
+        )),
         area,
+        modal: true,
     }
 }
 
 struct ConfirmMenu {
+    id:             PopupId,
     message:        String,
     cancel_button:  ButtonWidget,
     confirm_button: ButtonWidget,
     focus_index:    u8,
+    callback:       Option<ConfirmCallback>,
 }
 
 impl ConfirmMenu {
-    fn new(message: String, callback: fn(&Context)) -> Self {
+    fn new(id: PopupId, message: String, callback: ConfirmCallback) -> Self {
         let cancel_button = ButtonWidget::new("Cancel", |_| {})
             .set_selected(true)
             .to_owned();
-        let confirm_button = ButtonWidget::new("Confirm", callback);
+        let confirm_button = ButtonWidget::new("Confirm", |_| {});
 
         Self {
+            id,
             message,
             cancel_button,
             confirm_button,
             focus_index: 0,
+            callback: Some(callback),
         }
     }
 
-    fn handle_key(&mut self, key: KeyEvent, ctx: &Context) {
-        match key.code {
-            KeyCode::Left => {
+    fn handle_action(&mut self, action: Action, ctx: &Context) {
+        match action {
+            Action::SelectLeft => {
                 if self.focus_index == 0 {
                     self.focus_index = 1;
                 } else {
@@ -77,48 +91,70 @@ impl ConfirmMenu {
                 self.cancel_button.set_selected(self.focus_index == 0);
                 self.confirm_button.set_selected(self.focus_index == 1);
             },
-            KeyCode::Right => {
+            Action::SelectRight => {
                 self.focus_index += 1;
                 self.focus_index %= 2;
 
                 self.cancel_button.set_selected(self.focus_index == 0);
                 self.confirm_button.set_selected(self.focus_index == 1);
             },
-            KeyCode::Enter => {
-                self.cancel_button.on_key(ctx, key);
-                self.confirm_button.on_key(ctx, key);
+            Action::Submit => {
+                // Hide the popup first so that any popups shown
+                // by the callback aren't closed
+                ctx.send_notification(Notification::HidePopup(self.id));
 
-                // TODO: Logging
-                let _ = ctx.send_notification(Notification::HidePopup);
+                if self.confirm_button.has_focus() {
+                    if let Some(callback) = self.callback.take() {
+                        callback(ctx);
+                    }
+                }
+            },
+            // Same as choosing cancel
+            Action::ClosePopup => {
+                ctx.send_notification(Notification::HidePopup(self.id));
             },
             _ => {},
         }
     }
+
+    // Clicking a button selects and submits it
+    fn handle_mouse(&mut self, event: MouseEvent, ctx: &Context) {
+        if event.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+
+        let (column, row) = (event.column, event.row);
+        self.focus_index =
+            if area_contains(self.cancel_button.get_area(), column, row) {
+                0
+            } else if area_contains(self.confirm_button.get_area(), column, row)
+            {
+                1
+            } else {
+                return;
+            };
+
+        self.cancel_button.set_selected(self.focus_index == 0);
+        self.confirm_button.set_selected(self.focus_index == 1);
+        self.handle_action(Action::Submit, ctx);
+    }
 }
 
 impl Menu for ConfirmMenu {
     fn on_event(&mut self, event: Event, ctx: &Context) {
-        if let Event::Key(key) = event {
-            self.handle_key(key, ctx);
+        match event {
+            Event::Action(action) => self.handle_action(action, ctx),
+            Event::Mouse(event) => self.handle_mouse(event, ctx),
+            _ => {},
         }
     }
 
-    fn get_help_message(
-        &mut self,
-        _ctx: &Context,
-    ) -> Vec<(KeyModifiers, KeyCode, String)> {
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
         vec![
-            (KeyModifiers::NONE, KeyCode::Left, "Select left".to_string()),
-            (
-                KeyModifiers::NONE,
-                KeyCode::Right,
-                "Select right".to_string(),
-            ),
-            (
-                KeyModifiers::NONE,
-                KeyCode::Enter,
-                "Confirm selection".to_string(),
-            ),
+            Action::SelectLeft,
+            Action::SelectRight,
+            Action::Submit,
+            Action::ClosePopup,
         ]
     }
 
@@ -126,7 +162,7 @@ impl Menu for ConfirmMenu {
         &mut self,
         frame: &mut CrosstermFrame,
         mut max_size: Rect,
-        _ctx: &Context,
+        ctx: &Context,
     ) {
         let (_title_width, title_height) =
             format_padding(&CONFIRM_TITLE.to_owned(), TITLE_SPACING.to_owned());
@@ -163,8 +199,8 @@ impl Menu for ConfirmMenu {
         );
 
         let button_split = split_rect(50, Direction::Horizontal, split[2]);
-        self.confirm_button.render(button_split[0], frame);
-        self.cancel_button.render(button_split[1], frame);
+        self.confirm_button.render(button_split[0], frame, ctx);
+        self.cancel_button.render(button_split[1], frame, ctx);
     }
 
     fn get_minimum_size(&mut self) -> (u16, u16) {
@@ -186,3 +222,37 @@ fn format_padding(message: &str, padding: Spacing) -> (u16, u16) {
 
     (width, height)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::app::ui::widget::test::context;
+
+    #[test]
+    fn close_popup_cancels() {
+        let (ctx, receiver) = context();
+        let confirmed = Arc::new(AtomicBool::new(false));
+        let callback = {
+            let confirmed = confirmed.clone();
+            Box::new(move |_: &Context| confirmed.store(true, Ordering::SeqCst))
+        };
+        let id = PopupId::next();
+        let mut menu = ConfirmMenu::new(id, "Quit?".to_string(), callback);
+
+        // Moves focus to confirm so closing can't be mistaken
+        // for submitting cancel
+        menu.on_event(Event::Action(Action::SelectRight), &ctx);
+        menu.on_event(Event::Action(Action::ClosePopup), &ctx);
+
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Notification::HidePopup(hidden)) if hidden == id
+        ));
+        assert!(!confirmed.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,181 @@
+use tracing::Level;
+use tui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    widgets::Paragraph,
+};
+
+use super::{Popup, PopupArea, PopupId};
+use crate::{
+    app::{
+        context::{Context, Notification},
+        event::Event,
+        helper::{centered_rect, CenterPosition, CrosstermFrame},
+        keymap::Action,
+        ui::prelude::{
+            list::ListWidget, radio::RadioGroupWidget, Menu, Widget,
+        },
+    },
+    logging::{log_count, recent_logs, LogEntry},
+};
+
+// Ordered from least to most verbose, entries are shown if
+// they're at or before the chosen level
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+pub fn new_log_popup() -> Popup {
+    let options = LEVELS
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>();
+
+    let id = PopupId::next();
+    let mut menu = LogMenu {
+        id,
+        level: RadioGroupWidget::new("Level", options)
+            .set_chosen(LEVELS.len() - 1)
+            .set_split(20)
+            .set_selected(true)
+            .to_owned(),
+        entries: ListWidget::new(Vec::new()).set_selected(true).to_owned(),
+        log_count: 0,
+    };
+    menu.update_entries();
+
+    // Non-modal so the app keeps running while tailing
+    let mut popup = Popup::new(
+        id,
+        Box::new(menu),
+        PopupArea::Dynamic(|area| {
+            centered_rect(CenterPosition::Percentage(80, 70), area)
+        }),
+    );
+    popup.set_modal(false);
+
+    popup
+}
+
+struct LogMenu {
+    id:        PopupId,
+    level:     RadioGroupWidget,
+    entries:   ListWidget<LogEntry>,
+    // Number of entries logged when the list was last
+    // updated, used to check for new entries
+    log_count: usize,
+}
+
+impl LogMenu {
+    // Keeps following the newest entry if it was selected
+    fn update_entries(&mut self) {
+        let following = self.entries.items.is_empty()
+            || self.entries.selected + 1 == self.entries.items.len();
+
+        let levels = &LEVELS[..=self.level.chosen];
+        let entries = recent_logs()
+            .into_iter()
+            .filter(|entry| levels.contains(&entry.level))
+            .collect::<Vec<LogEntry>>();
+
+        self.entries.set_items(entries);
+        if following {
+            self.entries.select(self.entries.items.len());
+        }
+        self.log_count = log_count();
+    }
+
+    fn change_level(&mut self, action: Action, ctx: &Context) {
+        let previous = self.level.chosen;
+        self.level.on_action(ctx, action);
+
+        if self.level.chosen != previous {
+            self.update_entries();
+        }
+    }
+}
+
+impl Menu for LogMenu {
+    fn on_event(&mut self, event: Event, ctx: &Context) {
+        match event {
+            Event::Tick => {
+                if log_count() != self.log_count {
+                    self.update_entries();
+                }
+            },
+            Event::Action(action) => match action {
+                Action::FocusPrevious => {
+                    self.entries.on_action(ctx, Action::MoveUp);
+                },
+                Action::FocusNext => {
+                    self.entries.on_action(ctx, Action::MoveDown);
+                },
+                Action::SelectLeft | Action::SelectRight => {
+                    self.change_level(action, ctx);
+                },
+                Action::ClosePopup => {
+                    ctx.send_notification(Notification::HidePopup(self.id));
+                },
+                _ => self.entries.on_action(ctx, action),
+            },
+            Event::Mouse(event) => {
+                let previous = self.level.chosen;
+                self.level.on_mouse(ctx, event);
+                self.entries.on_mouse(ctx, event);
+
+                if self.level.chosen != previous {
+                    self.update_entries();
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
+        vec![
+            Action::FocusPrevious,
+            Action::FocusNext,
+            Action::SelectLeft,
+            Action::SelectRight,
+            Action::ClosePopup,
+            Action::PageUp,
+            Action::PageDown,
+            Action::First,
+            Action::Last,
+        ]
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut CrosstermFrame,
+        max_size: Rect,
+        ctx: &Context,
+    ) {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ])
+            .split(max_size);
+
+        self.level.render(split[0], frame, ctx);
+
+        if self.entries.items.is_empty() {
+            let block = Paragraph::new("No log entries.")
+                .style(Style::default().fg(ctx.theme.palette.muted));
+            frame.render_widget(block, split[2]);
+        } else {
+            self.entries.render(split[2], frame, ctx);
+        }
+    }
+
+    fn get_minimum_size(&mut self) -> (u16, u16) {
+        (0, 0)
+    }
+}
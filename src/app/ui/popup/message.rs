@@ -1,15 +1,16 @@
-use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::event::MouseEventKind;
 use tui::{
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Modifier, Style},
     widgets::{Paragraph, Wrap},
 };
 
-use super::{Popup, PopupArea, PopupPosition};
+use super::{Popup, PopupArea, PopupId, PopupPosition};
 use crate::app::{
-    context::Context,
+    context::{Context, Notification},
     event::Event,
-    helper::{shrink_area, CrosstermFrame, Spacing},
+    helper::{shrink_area, wrapped_height, CrosstermFrame, Spacing},
+    keymap::Action,
     ui::prelude::Menu,
 };
 
@@ -22,6 +23,7 @@ pub struct PopupMessageBuilder {
     position:        PopupPosition,
     message_padding: Spacing,
     title_padding:   Spacing,
+    modal:           bool,
 }
 
 #[allow(dead_code)]
@@ -37,6 +39,7 @@ impl PopupMessageBuilder {
             position:        PopupPosition::Center,
             message_padding: Spacing::new(1, 1, 4, 4),
             title_padding:   Spacing::default(),
+            modal:           true,
         }
     }
 
@@ -56,16 +59,22 @@ impl PopupMessageBuilder {
             PopupArea::Absolute(width, height, self.position)
         };
 
+        let id = PopupId::next();
         Popup {
+            id,
             menu: Box::new(MessageMenu {
-                message:         self.message.clone(),
-                message_align:   self.message_align,
+                id,
+                message: self.message.clone(),
+                message_align: self.message_align,
                 message_padding: self.message_padding,
-                title:           self.title.clone(),
-                title_align:     self.title_align,
-                title_padding:   self.title_padding,
+                scroll: 0,
+                message_area: Rect::default(),
+                title: self.title.clone(),
+                title_align: self.title_align,
+                title_padding: self.title_padding,
             }),
             area,
+            modal: self.modal,
         }
     }
 
@@ -76,6 +85,11 @@ impl PopupMessageBuilder {
         self
     }
 
+    pub fn set_modal(&mut self, modal: bool) -> &mut Self {
+        self.modal = modal;
+        self
+    }
+
     pub fn set_title_align(&mut self, title_align: Alignment) -> &mut Self {
         self.title_align = title_align;
         self
@@ -127,12 +141,27 @@ impl PopupMessageBuilder {
 }
 
 struct MessageMenu {
-    message:         String,
-    message_align:   Alignment,
-    title:           Option<String>,
-    title_align:     Alignment,
+    id: PopupId,
+    message: String,
+    message_align: Alignment,
+    scroll: u16,
+    // Where the message was last drawn, used to limit
+    // scrolling to the wrapped text
+    message_area: Rect,
+    title: Option<String>,
+    title_align: Alignment,
     message_padding: Spacing,
-    title_padding:   Spacing,
+    title_padding: Spacing,
+}
+
+impl MessageMenu {
+    // Stops once the last line is at the bottom
+    fn max_scroll(&self) -> u16 {
+        let height =
+            wrapped_height(&self.message, self.message_area.width) as u16;
+
+        height.saturating_sub(self.message_area.height)
+    }
 }
 
 impl Menu for MessageMenu {
@@ -167,30 +196,43 @@ impl Menu for MessageMenu {
             max_size = split[1];
         }
 
+        self.message_area = shrink_area(max_size, self.message_padding);
+        // The area may have shrunk since the last scroll
+        self.scroll = self.scroll.min(self.max_scroll());
+
         let block = Paragraph::new(self.message.clone())
             .wrap(Wrap {
                 trim: false,
             })
-            .alignment(self.message_align);
-        frame.render_widget(block, shrink_area(max_size, self.message_padding));
+            .alignment(self.message_align)
+            .scroll((self.scroll, 0));
+        frame.render_widget(block, self.message_area);
     }
 
     fn on_event(&mut self, event: Event, ctx: &Context) {
-        if let Event::Key(key) = event {
-            if key.code == KeyCode::Esc {
-                // TODO: Logging
-                let _ = ctx.send_notification(
-                    crate::app::context::Notification::HidePopup,
-                );
-            }
+        match event {
+            Event::Action(Action::ClosePopup) => {
+                ctx.send_notification(Notification::HidePopup(self.id));
+            },
+            Event::Mouse(event) => {
+                // Allows reading messages which are taller than
+                // the screen
+                match event.kind {
+                    MouseEventKind::ScrollDown => {
+                        self.scroll = (self.scroll + 1).min(self.max_scroll());
+                    },
+                    MouseEventKind::ScrollUp => {
+                        self.scroll = self.scroll.saturating_sub(1);
+                    },
+                    _ => {},
+                }
+            },
+            _ => {},
         }
     }
 
-    fn get_help_message(
-        &mut self,
-        _ctx: &Context,
-    ) -> Vec<(KeyModifiers, KeyCode, String)> {
-        vec![(KeyModifiers::NONE, KeyCode::Esc, "Close popup".to_string())]
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
+        vec![Action::ClosePopup]
     }
 
     fn get_minimum_size(&mut self) -> (u16, u16) {
@@ -1,4 +1,5 @@
-use crossterm::event::{KeyCode, KeyModifiers};
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use tui::layout::{Constraint, Direction, Layout, Rect};
 
 use super::prelude::Menu;
@@ -6,10 +7,17 @@ use crate::app::{
     context::Context,
     event::Event,
     helper::{centered_rect, CenterPosition, CrosstermFrame},
+    keymap::Action,
 };
 
 pub mod confirmation;
+pub mod logs;
 pub mod message;
+pub mod palette;
+pub mod prompt;
+pub mod search;
+pub mod switcher;
+pub mod toast;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -31,7 +39,7 @@ impl Default for PopupPosition {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PopupArea {
     // The absolute x, y and position
     Absolute(u16, u16, PopupPosition),
@@ -39,7 +47,9 @@ pub enum PopupArea {
     //   size minus the help menu) and return the
     //   desired size
     Dynamic(fn(Rect) -> Rect),
-    //
+    // Shown below the anchor with the given height, or above
+    // it if there isn't enough space below
+    Anchored(Rect, u16),
 }
 
 impl Default for PopupArea {
@@ -50,156 +60,185 @@ impl Default for PopupArea {
     }
 }
 
+static NEXT_POPUP_ID: AtomicU64 = AtomicU64::new(0);
+
+// Identifies a popup so that hiding it doesn't close a
+// different one shown on top of it in the meantime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PopupId(u64);
+
+impl PopupId {
+    pub fn next() -> Self {
+        Self(NEXT_POPUP_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
 pub struct Popup {
-    menu: Box<dyn Menu + Send>,
-    area: PopupArea,
+    id:    PopupId,
+    menu:  Box<dyn Menu + Send>,
+    area:  PopupArea,
+    // Modal popups stop anything underneath them from
+    // receiving ticks
+    modal: bool,
 }
 
 impl Popup {
+    pub fn new(
+        id: PopupId,
+        menu: Box<dyn Menu + Send>,
+        area: PopupArea,
+    ) -> Self {
+        Self {
+            id,
+            menu,
+            area,
+            modal: true,
+        }
+    }
+
+    pub fn id(&self) -> PopupId {
+        self.id
+    }
+
+    pub fn is_modal(&self) -> bool {
+        self.modal
+    }
+
+    pub fn set_modal(&mut self, modal: bool) -> &mut Self {
+        self.modal = modal;
+        self
+    }
+
     pub fn get_area(&self, frame_size: Rect) -> Rect {
         match self.area {
             PopupArea::Dynamic(func) => func(frame_size),
             PopupArea::Absolute(width, height, pos) => {
-                let max_size = centered_rect(
-                    CenterPosition::AbsoluteOutter(2, 2),
-                    frame_size,
-                );
-
-                match pos {
-                    PopupPosition::TopLeft => {
-                        let top = Layout::default()
-                            .direction(Direction::Vertical)
-                            .constraints([
-                                Constraint::Length(height),
-                                Constraint::Min(0),
-                            ])
-                            .split(max_size)[0];
-
-                        Layout::default()
-                            .direction(Direction::Horizontal)
-                            .constraints([
-                                Constraint::Length(width),
-                                Constraint::Min(0),
-                            ])
-                            .split(top)[0]
-                    },
-                    PopupPosition::Top => {
-                        let top = Layout::default()
-                            .direction(Direction::Vertical)
-                            .constraints([
-                                Constraint::Length(height),
-                                Constraint::Min(0),
-                            ])
-                            .split(max_size)[0];
-
-                        centered_rect(
-                            CenterPosition::AbsoluteInner(width, height),
-                            top,
-                        )
-                    },
-                    PopupPosition::TopRight => {
-                        let top = Layout::default()
-                            .direction(Direction::Vertical)
-                            .constraints([
-                                Constraint::Length(height),
-                                Constraint::Min(0),
-                            ])
-                            .split(max_size)[0];
-
-                        Layout::default()
-                            .direction(Direction::Horizontal)
-                            .constraints([
-                                Constraint::Min(0),
-                                Constraint::Length(width),
-                            ])
-                            .split(top)[1]
-                    },
-                    PopupPosition::Left => {
-                        let left = Layout::default()
-                            .direction(Direction::Horizontal)
-                            .constraints([
-                                Constraint::Length(width),
-                                Constraint::Min(0),
-                            ])
-                            .split(max_size)[0];
-
-                        centered_rect(
-                            CenterPosition::AbsoluteInner(width, height),
-                            left,
-                        )
-                    },
-                    PopupPosition::Center => centered_rect(
-                        CenterPosition::AbsoluteInner(width, height),
-                        max_size,
-                    ),
-                    PopupPosition::Right => {
-                        let right = Layout::default()
-                            .direction(Direction::Horizontal)
-                            .constraints([
-                                Constraint::Min(0),
-                                Constraint::Length(width),
-                            ])
-                            .split(max_size)[1];
-
-                        centered_rect(
-                            CenterPosition::AbsoluteInner(width, height),
-                            right,
-                        )
-                    },
-                    PopupPosition::BottomLeft => {
-                        let bottom = Layout::default()
-                            .direction(Direction::Vertical)
-                            .constraints([
-                                Constraint::Min(0),
-                                Constraint::Length(height),
-                            ])
-                            .split(max_size)[1];
-
-                        Layout::default()
-                            .direction(Direction::Horizontal)
-                            .constraints([
-                                Constraint::Length(width),
-                                Constraint::Min(0),
-                            ])
-                            .split(bottom)[0]
-                    },
-                    PopupPosition::Bottom => {
-                        let bottom = Layout::default()
-                            .direction(Direction::Vertical)
-                            .constraints([
-                                Constraint::Min(0),
-                                Constraint::Length(height),
-                            ])
-                            .split(max_size)[1];
-
-                        centered_rect(
-                            CenterPosition::AbsoluteInner(width, height),
-                            bottom,
-                        )
-                    },
-                    PopupPosition::BottomRight => {
-                        let bottom = Layout::default()
-                            .direction(Direction::Vertical)
-                            .constraints([
-                                Constraint::Min(0),
-                                Constraint::Length(height),
-                            ])
-                            .split(max_size)[1];
-
-                        Layout::default()
-                            .direction(Direction::Horizontal)
-                            .constraints([
-                                Constraint::Min(0),
-                                Constraint::Length(width),
-                            ])
-                            .split(bottom)[1]
-                    },
-                }
+                get_absolute_area(width, height, pos, frame_size)
+            },
+            PopupArea::Anchored(anchor, height) => {
+                get_anchored_area(anchor, height, frame_size)
             },
         }
     }
 }
 
+// Area inside of the border of a popup which lines up with
+// the anchor
+fn get_anchored_area(anchor: Rect, height: u16, frame_size: Rect) -> Rect {
+    let x = anchor.x + 1;
+    let width = anchor.width.saturating_sub(2);
+
+    // 1 for the border on each side
+    let space_below = frame_size.bottom().saturating_sub(anchor.bottom() + 2);
+    let space_above = anchor.y.saturating_sub(frame_size.y + 2);
+
+    if height <= space_below || space_below >= space_above {
+        let height = height.min(space_below);
+        Rect::new(x, anchor.bottom() + 1, width, height)
+    } else {
+        let height = height.min(space_above);
+        Rect::new(x, anchor.y - height - 1, width, height)
+    }
+}
+
+// Position an area of the given size inside of the frame,
+// leaving space for a border
+pub fn get_absolute_area(
+    width: u16,
+    height: u16,
+    pos: PopupPosition,
+    frame_size: Rect,
+) -> Rect {
+    let max_size =
+        centered_rect(CenterPosition::AbsoluteOutter(2, 2), frame_size);
+    // Shrink the popup if the terminal is too small
+    let width = width.min(max_size.width);
+    let height = height.min(max_size.height);
+
+    match pos {
+        PopupPosition::TopLeft => {
+            let top = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(height), Constraint::Min(0)])
+                .split(max_size)[0];
+
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(width), Constraint::Min(0)])
+                .split(top)[0]
+        },
+        PopupPosition::Top => {
+            let top = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(height), Constraint::Min(0)])
+                .split(max_size)[0];
+
+            centered_rect(CenterPosition::AbsoluteInner(width, height), top)
+        },
+        PopupPosition::TopRight => {
+            let top = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(height), Constraint::Min(0)])
+                .split(max_size)[0];
+
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(width)])
+                .split(top)[1]
+        },
+        PopupPosition::Left => {
+            let left = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(width), Constraint::Min(0)])
+                .split(max_size)[0];
+
+            centered_rect(CenterPosition::AbsoluteInner(width, height), left)
+        },
+        PopupPosition::Center => centered_rect(
+            CenterPosition::AbsoluteInner(width, height),
+            max_size,
+        ),
+        PopupPosition::Right => {
+            let right = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(width)])
+                .split(max_size)[1];
+
+            centered_rect(CenterPosition::AbsoluteInner(width, height), right)
+        },
+        PopupPosition::BottomLeft => {
+            let bottom = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(height)])
+                .split(max_size)[1];
+
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(width), Constraint::Min(0)])
+                .split(bottom)[0]
+        },
+        PopupPosition::Bottom => {
+            let bottom = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(height)])
+                .split(max_size)[1];
+
+            centered_rect(CenterPosition::AbsoluteInner(width, height), bottom)
+        },
+        PopupPosition::BottomRight => {
+            let bottom = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(height)])
+                .split(max_size)[1];
+
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(width)])
+                .split(bottom)[1]
+        },
+    }
+}
+
 impl Menu for Popup {
     fn draw(
         &mut self,
@@ -214,14 +253,15 @@ impl Menu for Popup {
         self.menu.on_event(event, ctx)
     }
 
-    fn get_help_message(
-        &mut self,
-        ctx: &Context,
-    ) -> Vec<(KeyModifiers, KeyCode, String)> {
-        self.menu.get_help_message(ctx)
+    fn get_actions(&mut self, ctx: &Context) -> Vec<Action> {
+        self.menu.get_actions(ctx)
     }
 
     fn get_minimum_size(&mut self) -> (u16, u16) {
         self.menu.get_minimum_size()
     }
+
+    fn get_name(&self) -> &'static str {
+        self.menu.get_name()
+    }
 }
@@ -0,0 +1,82 @@
+use std::cmp::Reverse;
+
+use super::{search::new_search_popup, Popup};
+use crate::app::{
+    context::Notification,
+    fuzzy::best_score,
+    keymap::{Action, Keymap},
+};
+
+#[derive(Debug, Clone)]
+struct PaletteEntry {
+    action:    Action,
+    keys:      String,
+    available: bool,
+}
+
+// Lists every action with its keybinding, actions which
+// can't be used from the current menu are still listed so
+// they can be discovered
+pub fn new_palette_popup(keymap: &Keymap, available: &[Action]) -> Popup {
+    let entries = Action::ALL
+        .iter()
+        .filter(|action| **action != Action::CommandPalette)
+        .map(|action| PaletteEntry {
+            action:    *action,
+            keys:      keymap
+                .get_bindings(*action)
+                .first()
+                .map_or_else(String::new, ToString::to_string),
+            available: available.contains(action),
+        })
+        .collect::<Vec<PaletteEntry>>();
+
+    new_search_popup(
+        move |query| rank_entries(&entries, query),
+        select_entry,
+        format_entry,
+        "No matching commands.",
+    )
+}
+
+fn format_entry(entry: &PaletteEntry) -> String {
+    let mut text = entry.action.description();
+    if !entry.keys.is_empty() {
+        text = format!("{} [{}]", text, entry.keys);
+    }
+    if !entry.available {
+        text += " (unavailable)";
+    }
+
+    text
+}
+
+// Available actions are listed before unavailable ones with
+// the best matches first
+fn rank_entries(entries: &[PaletteEntry], query: &str) -> Vec<PaletteEntry> {
+    let mut ranked = entries
+        .iter()
+        .filter_map(|entry| {
+            let description = entry.action.description();
+            let names = vec![description.as_str(), entry.action.name()];
+            best_score(query, names).map(|score| (score, entry))
+        })
+        .collect::<Vec<(i64, &PaletteEntry)>>();
+    ranked.sort_by_key(|(score, entry)| (!entry.available, Reverse(*score)));
+
+    ranked
+        .into_iter()
+        .map(|(_score, entry)| entry.clone())
+        .collect()
+}
+
+fn select_entry(entry: &PaletteEntry) -> Result<Notification, String> {
+    if entry.available {
+        Ok(Notification::RunAction(entry.action))
+    } else {
+        Err(format!(
+            "{} isn't available here.",
+            entry.action.description()
+        ))
+    }
+}
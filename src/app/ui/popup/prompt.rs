@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use tui::{
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    widgets::Paragraph,
+};
+
+use super::{Popup, PopupArea, PopupId, PopupPosition};
+use crate::app::{
+    context::{Context, Notification},
+    event::Event,
+    helper::{centered_line, CrosstermFrame},
+    keymap::Action,
+    ui::prelude::{
+        ButtonWidget, Form, FormResult, LabeledInputWidget, Menu, Severity,
+        Toast, ValidationType,
+    },
+};
+
+const PROMPT_WIDTH: u16 = 50;
+// Labels take up this much of each line, the rest is the
+// input which should fit at least a few characters
+const LABEL_PERCENTAGE: u16 = 40;
+const MIN_INPUT_WIDTH: usize = 8;
+// Name of the submit button in the form, wrapped in angle
+// brackets so it doesn't clash with the prompt's fields
+const PROMPT_SUBMIT: &str = "<submit>";
+
+// Values entered into the prompt keyed by field name
+pub type PromptValues = HashMap<String, String>;
+pub type PromptCallback = Box<dyn FnOnce(&Context, PromptValues) + Send>;
+
+#[derive(Debug, Clone)]
+struct PromptField {
+    name:       String,
+    label:      String,
+    value:      String,
+    secret:     bool,
+    validation: ValidationType,
+    error:      Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PopupPromptBuilder {
+    title:    String,
+    fields:   Vec<PromptField>,
+    submit:   String,
+    position: PopupPosition,
+}
+
+#[allow(dead_code)]
+impl PopupPromptBuilder {
+    pub fn new<T>(title: T) -> Self
+    where
+        T: ToString, {
+        Self {
+            title:    title.to_string(),
+            fields:   Vec::new(),
+            submit:   "Submit".to_string(),
+            position: PopupPosition::Center,
+        }
+    }
+
+    pub fn to_popup<F>(&self, callback: F) -> Popup
+    where
+        F: FnOnce(&Context, PromptValues) + Send + 'static, {
+        let mut form = Form::default();
+        for field in &self.fields {
+            let mut input = LabeledInputWidget::new(&field.label)
+                .set_split(LABEL_PERCENTAGE)
+                .set_secret(field.secret)
+                .set_validation(field.validation.clone())
+                .to_owned();
+            input.input.set_value(&field.value);
+
+            form.add_field(&field.name, input);
+            if let Some(error) = &field.error {
+                form.set_error(&field.name, error);
+            }
+        }
+        form.add_field(PROMPT_SUBMIT, ButtonWidget::new(&self.submit, |_| {}))
+            .set_submit(PROMPT_SUBMIT, true);
+
+        // 2 for the title and 2 for the spacing and button
+        let height = self.fields.len() as u16 + 4;
+        // Long labels or titles widen the prompt so it isn't
+        // drawn as too small
+        let width = PROMPT_WIDTH.max(self.minimum_width());
+
+        let id = PopupId::next();
+        Popup {
+            id,
+            menu: Box::new(PromptMenu {
+                id,
+                title: self.title.clone(),
+                names: self.fields.iter().map(|f| f.name.clone()).collect(),
+                form,
+                callback: Some(Box::new(callback)),
+                minimum_size: (self.minimum_width(), height),
+            }),
+            area: PopupArea::Absolute(width, height, self.position),
+            modal: true,
+        }
+    }
+
+    pub fn add_field<T, U>(&mut self, name: T, label: U) -> &mut Self
+    where
+        T: ToString,
+        U: ToString, {
+        self.fields.push(PromptField {
+            name:       name.to_string(),
+            label:      label.to_string(),
+            value:      String::default(),
+            secret:     false,
+            validation: ValidationType::default(),
+            error:      None,
+        });
+        self
+    }
+
+    pub fn set_value<T: ToString>(
+        &mut self,
+        name: &str,
+        value: T,
+    ) -> &mut Self {
+        if let Some(field) = self.get_field(name) {
+            field.value = value.to_string();
+        }
+        self
+    }
+
+    pub fn set_secret(&mut self, name: &str, secret: bool) -> &mut Self {
+        if let Some(field) = self.get_field(name) {
+            field.secret = secret;
+        }
+        self
+    }
+
+    // Error is shown when the value fails validation
+    pub fn set_validation<T: ToString>(
+        &mut self,
+        name: &str,
+        validation: ValidationType,
+        error: T,
+    ) -> &mut Self {
+        if let Some(field) = self.get_field(name) {
+            field.validation = validation;
+            field.error = Some(error.to_string());
+        }
+        self
+    }
+
+    pub fn set_submit<T: ToString>(&mut self, submit: T) -> &mut Self {
+        self.submit = submit.to_string();
+        self
+    }
+
+    pub fn set_position(&mut self, position: PopupPosition) -> &mut Self {
+        self.position = position;
+        self
+    }
+
+    // Wide enough for the title, each label next to a short
+    // input and the button, along with the margin used when
+    // drawing
+    fn minimum_width(&self) -> u16 {
+        let label_width = self
+            .fields
+            .iter()
+            .map(|field| field.label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let label_percentage = LABEL_PERCENTAGE as usize;
+        let field_width = (label_width * 100)
+            .div_ceil(label_percentage)
+            .max((MIN_INPUT_WIDTH * 100).div_ceil(100 - label_percentage));
+        let title_width = self.title.chars().count();
+        // The button is drawn with brackets around it
+        let button_width = self.submit.chars().count() + 2;
+
+        let width = field_width.max(title_width).max(button_width) + 4;
+        width.min(u16::MAX as usize) as u16
+    }
+
+    fn get_field(&mut self, name: &str) -> Option<&mut PromptField> {
+        self.fields.iter_mut().find(|field| field.name == name)
+    }
+}
+
+struct PromptMenu {
+    id:           PopupId,
+    title:        String,
+    names:        Vec<String>,
+    form:         Form,
+    callback:     Option<PromptCallback>,
+    minimum_size: (u16, u16),
+}
+
+impl PromptMenu {
+    fn handle_result(&mut self, result: FormResult, ctx: &Context) {
+        match result {
+            FormResult::Invalid(errors) => {
+                let toast = Toast::new(errors.join(" "), Severity::Warning);
+                ctx.send_notification(Notification::ShowToast(toast));
+            },
+            FormResult::Submit => {
+                let values = self
+                    .names
+                    .iter()
+                    .map(|name| {
+                        let value =
+                            self.form.get_value(name).unwrap_or_default();
+                        (name.clone(), value)
+                    })
+                    .collect();
+
+                ctx.send_notification(Notification::HidePopup(self.id));
+
+                if let Some(callback) = self.callback.take() {
+                    callback(ctx, values);
+                }
+            },
+            FormResult::None => {},
+        }
+    }
+}
+
+impl Menu for PromptMenu {
+    fn on_event(&mut self, event: Event, ctx: &Context) {
+        match event {
+            Event::Tick => self.form.on_tick(ctx),
+            Event::Key(key) => self.form.on_key(ctx, key),
+            Event::Action(Action::ClosePopup) => {
+                ctx.send_notification(Notification::HidePopup(self.id));
+            },
+            Event::Action(action) => {
+                let result = self.form.on_action(ctx, action);
+                self.handle_result(result, ctx);
+            },
+            Event::Mouse(event) => {
+                let result = self.form.on_mouse(ctx, event);
+                self.handle_result(result, ctx);
+            },
+            _ => {},
+        }
+    }
+
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
+        let mut actions = self.form.get_actions();
+        actions.push(Action::ClosePopup);
+        actions
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut CrosstermFrame,
+        max_size: Rect,
+        ctx: &Context,
+    ) {
+        let width = max_size.width.saturating_sub(4);
+        let title = Paragraph::new(self.title.clone())
+            .alignment(Alignment::Center)
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        frame.render_widget(title, centered_line(width, 1, 0, max_size));
+
+        // Inputs start after the title and the submit button
+        // is separated by an empty line
+        let fields = self.names.len() as u16;
+        let mut areas = (0..fields)
+            .map(|idx| centered_line(width, 1, idx + 2, max_size))
+            .collect::<Vec<Rect>>();
+        areas.push(centered_line(width, 1, fields + 3, max_size));
+
+        self.form.render(&areas, frame, ctx);
+    }
+
+    fn get_minimum_size(&mut self) -> (u16, u16) {
+        self.minimum_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::layout::Rect;
+
+    use super::*;
+    use crate::app::ui::prelude::Menu;
+
+    // Menus can only be drawn to crossterm, so this checks the
+    // area the popup gets against its minimum size the same
+    // way the app does before drawing it
+    fn fits_on_screen(popup: &mut Popup, width: u16, height: u16) -> bool {
+        let area = popup.get_area(Rect::new(0, 0, width, height));
+        let (min_width, min_height) = popup.get_minimum_size();
+        min_width <= area.width && min_height <= area.height
+    }
+
+    #[test]
+    fn short_labels_use_default_width() {
+        let mut popup = PopupPromptBuilder::new("Join")
+            .add_field("room", "Room")
+            .to_owned()
+            .to_popup(|_, _| {});
+
+        let area = popup.get_area(Rect::new(0, 0, 120, 40));
+        assert_eq!(area.width, PROMPT_WIDTH);
+        assert!(fits_on_screen(&mut popup, 120, 40));
+    }
+
+    #[test]
+    fn long_labels_widen_prompt() {
+        let label = "A label which is far too long to fit in the prompt";
+        let mut popup = PopupPromptBuilder::new("Login")
+            .add_field("name", label)
+            .to_owned()
+            .to_popup(|_, _| {});
+
+        let area = popup.get_area(Rect::new(0, 0, 200, 40));
+        assert!(area.width > PROMPT_WIDTH);
+        assert!(fits_on_screen(&mut popup, 200, 40));
+    }
+}
@@ -0,0 +1,177 @@
+use crossterm::event::KeyEvent;
+use tui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    widgets::Paragraph,
+};
+
+use super::{Popup, PopupArea, PopupId};
+use crate::app::{
+    context::{Context, Notification},
+    event::Event,
+    helper::{centered_rect, CenterPosition, CrosstermFrame},
+    keymap::Action,
+    ui::prelude::{
+        list::ListWidget, InputWidget, Menu, Severity, Toast, Widget,
+    },
+};
+
+// Returns the items matching the query, best match first
+pub type SearchFn<T> = Box<dyn Fn(&str) -> Vec<T> + Send>;
+// Returns the notification to send once the popup is
+// hidden, or a warning to show if the item can't be chosen
+pub type SelectFn<T> = Box<dyn Fn(&T) -> Result<Notification, String> + Send>;
+
+// Text input above a list of the items matching it, used by
+// the command palette and the room switcher
+pub fn new_search_popup<T, S, F>(
+    search_fn: S,
+    select_fn: F,
+    format_fn: fn(&T) -> String,
+    empty_message: &str,
+) -> Popup
+where
+    T: Clone + Send + 'static,
+    S: Fn(&str) -> Vec<T> + Send + 'static,
+    F: Fn(&T) -> Result<Notification, String> + Send + 'static, {
+    let id = PopupId::next();
+    let mut menu = SearchMenu {
+        id,
+        query: InputWidget::default().set_selected(true).to_owned(),
+        results: ListWidget::with_format(Vec::new(), format_fn)
+            .set_selected(true)
+            .to_owned(),
+        search_fn: Box::new(search_fn),
+        select_fn: Box::new(select_fn),
+        empty_message: empty_message.to_string(),
+    };
+    menu.update_results();
+
+    Popup::new(
+        id,
+        Box::new(menu),
+        PopupArea::Dynamic(|area| {
+            centered_rect(CenterPosition::Percentage(60, 50), area)
+        }),
+    )
+}
+
+struct SearchMenu<T> {
+    id:            PopupId,
+    query:         InputWidget,
+    results:       ListWidget<T>,
+    search_fn:     SearchFn<T>,
+    select_fn:     SelectFn<T>,
+    empty_message: String,
+}
+
+impl<T> SearchMenu<T> {
+    fn update_results(&mut self) {
+        let items = (self.search_fn)(self.query.value.trim());
+
+        self.results.set_items(items);
+        self.results.select(0);
+    }
+
+    fn submit(&mut self, ctx: &Context) {
+        let item = match self.results.get_selected() {
+            Some(item) => item,
+            None => return,
+        };
+
+        match (self.select_fn)(item) {
+            // Hide the popup first so that actions go to
+            // whatever is underneath it
+            Ok(notification) => {
+                ctx.send_notification(Notification::HidePopup(self.id));
+                ctx.send_notification(notification);
+            },
+            Err(message) => {
+                let toast = Toast::new(message, Severity::Warning);
+                ctx.send_notification(Notification::ShowToast(toast));
+            },
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, ctx: &Context) {
+        let previous = self.query.value.clone();
+        self.query.on_key(ctx, key);
+
+        if self.query.value != previous {
+            self.update_results();
+        }
+    }
+}
+
+impl<T> Menu for SearchMenu<T> {
+    fn on_event(&mut self, event: Event, ctx: &Context) {
+        match event {
+            Event::Tick => self.query.on_tick(ctx),
+            Event::Key(key) => self.handle_key(key, ctx),
+            Event::Action(action) => match action {
+                Action::FocusPrevious => {
+                    self.results.on_action(ctx, Action::MoveUp);
+                },
+                Action::FocusNext => {
+                    self.results.on_action(ctx, Action::MoveDown);
+                },
+                Action::PageUp | Action::PageDown => {
+                    self.results.on_action(ctx, action);
+                },
+                Action::Submit => self.submit(ctx),
+                Action::ClosePopup => {
+                    ctx.send_notification(Notification::HidePopup(self.id));
+                },
+                _ => {},
+            },
+            Event::Mouse(event) => self.results.on_mouse(ctx, event),
+            _ => {},
+        }
+    }
+
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
+        vec![
+            Action::FocusPrevious,
+            Action::FocusNext,
+            Action::PageUp,
+            Action::PageDown,
+            Action::Submit,
+            Action::ClosePopup,
+        ]
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut CrosstermFrame,
+        max_size: Rect,
+        ctx: &Context,
+    ) {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ])
+            .split(max_size);
+
+        let prompt = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(2), Constraint::Min(0)])
+            .split(split[0]);
+        frame.render_widget(Paragraph::new("> "), prompt[0]);
+        self.query.render(prompt[1], frame, ctx);
+
+        if self.results.items.is_empty() {
+            let block = Paragraph::new(self.empty_message.as_str())
+                .style(Style::default().fg(ctx.theme.palette.muted));
+            frame.render_widget(block, split[2]);
+        } else {
+            self.results.render(split[2], frame, ctx);
+        }
+    }
+
+    fn get_minimum_size(&mut self) -> (u16, u16) {
+        (0, 0)
+    }
+}
@@ -0,0 +1,183 @@
+use std::cmp::Reverse;
+
+use super::{search::new_search_popup, Popup};
+use crate::app::{
+    context::Notification,
+    fuzzy::best_score,
+    room::{RoomEntry, RoomKind},
+};
+
+// Member names are a weaker match than the room's own name
+const MEMBER_PENALTY: i64 = 24;
+// Bonus for the most recently viewed room, this decreases
+// for each room viewed before it
+const RECENT_BONUS: i64 = 40;
+const RECENT_STEP: i64 = 8;
+
+#[derive(Debug, Clone)]
+enum SwitcherItem {
+    Room(RoomEntry),
+    Join(String),
+}
+
+pub fn new_switcher_popup(rooms: Vec<RoomEntry>) -> Popup {
+    let empty_message = if rooms.is_empty() {
+        "No rooms joined yet."
+    } else {
+        "No matching rooms."
+    };
+
+    new_search_popup(
+        move |query| rank_rooms(&rooms, query),
+        select_item,
+        format_item,
+        empty_message,
+    )
+}
+
+fn format_item(item: &SwitcherItem) -> String {
+    match item {
+        SwitcherItem::Room(room) => {
+            let mut text = format!("{} {}", room.kind.icon(), room.name);
+            if let Some(alias) = &room.alias {
+                text = format!("{} ({})", text, alias);
+            }
+            if room.kind == RoomKind::Invite {
+                text += " [invite]";
+            }
+            text
+        },
+        SwitcherItem::Join(alias) => format!("Join {}", alias),
+    }
+}
+
+// Rank rooms by how well they match the query and how
+// recently they were viewed
+fn rank_rooms(rooms: &[RoomEntry], query: &str) -> Vec<SwitcherItem> {
+    let mut recent = rooms
+        .iter()
+        .filter_map(|room| room.last_viewed.map(|time| (time, &room.id)))
+        .collect::<Vec<_>>();
+    recent.sort_by_key(|(time, _id)| Reverse(*time));
+    let recency_bonus = |room: &RoomEntry| {
+        recent
+            .iter()
+            .position(|(_time, id)| **id == room.id)
+            .map_or(0, |idx| (RECENT_BONUS - RECENT_STEP * idx as i64).max(0))
+    };
+
+    let mut ranked = rooms
+        .iter()
+        .filter_map(|room| {
+            let names = std::iter::once(room.name.as_str())
+                .chain(room.alias.as_deref());
+            let room_score = best_score(query, names);
+            let member_score =
+                best_score(query, room.members.iter().map(String::as_str))
+                    .map(|score| score - MEMBER_PENALTY);
+
+            room_score
+                .max(member_score)
+                .map(|score| (score + recency_bonus(room), room))
+        })
+        .collect::<Vec<(i64, &RoomEntry)>>();
+    ranked.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.cmp(a_score).then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut items = ranked
+        .into_iter()
+        .map(|(_score, room)| SwitcherItem::Room(room.clone()))
+        .collect::<Vec<SwitcherItem>>();
+
+    // Offer to join the room if it isn't one we know of
+    if items.is_empty() && RoomEntry::is_alias(query) {
+        items.push(SwitcherItem::Join(query.to_string()));
+    }
+
+    items
+}
+
+fn select_item(item: &SwitcherItem) -> Result<Notification, String> {
+    Ok(match item {
+        SwitcherItem::Room(room) => Notification::OpenRoom(room.id.clone()),
+        SwitcherItem::Join(alias) => Notification::JoinRoom(alias.clone()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn room(name: &str, kind: RoomKind) -> RoomEntry {
+        RoomEntry {
+            id: format!("!{}:example.org", name.to_lowercase()),
+            name: name.to_string(),
+            alias: None,
+            kind,
+            members: Vec::new(),
+            unread: 0,
+            latest_event: None,
+            last_viewed: None,
+        }
+    }
+
+    fn names(items: &[SwitcherItem]) -> Vec<String> {
+        items.iter().map(format_item).collect()
+    }
+
+    fn rooms() -> Vec<RoomEntry> {
+        vec![
+            room("General", RoomKind::Room),
+            room("Gaming", RoomKind::Room),
+            room("Off-topic", RoomKind::Room),
+        ]
+    }
+
+    #[test]
+    fn best_match_first_and_non_matches_removed() {
+        let items = rank_rooms(&rooms(), "gen");
+        assert_eq!(names(&items), vec!["# General"]);
+
+        // Equal scores fall back to the name
+        let items = rank_rooms(&rooms(), "g");
+        assert_eq!(names(&items), vec!["# Gaming", "# General"]);
+    }
+
+    #[test]
+    fn recently_viewed_rooms_break_ties() {
+        let mut rooms = rooms();
+        let now = Instant::now();
+        rooms[0].last_viewed = Some(now);
+        rooms[1].last_viewed = Some(now + Duration::from_secs(1));
+
+        // Gaming was viewed last so it's ahead of General
+        let items = rank_rooms(&rooms, "g");
+        assert_eq!(names(&items), vec!["# Gaming", "# General"]);
+
+        rooms[0].last_viewed = Some(now + Duration::from_secs(2));
+        let items = rank_rooms(&rooms, "g");
+        assert_eq!(names(&items), vec!["# General", "# Gaming"]);
+    }
+
+    #[test]
+    fn members_match_direct_rooms() {
+        let mut direct = room("Chat", RoomKind::Direct);
+        direct.members = vec!["Alice Smith".to_string()];
+        let rooms = vec![direct, room("Alice's Room", RoomKind::Room)];
+
+        // The room's own name beats a member's name
+        let items = rank_rooms(&rooms, "alice");
+        assert_eq!(names(&items), vec!["# Alice's Room", "@ Chat"]);
+    }
+
+    #[test]
+    fn offers_to_join_unknown_aliases() {
+        let items = rank_rooms(&rooms(), "#rust:example.org");
+        assert_eq!(names(&items), vec!["Join #rust:example.org"]);
+
+        assert!(rank_rooms(&rooms(), "nothing").is_empty());
+    }
+}
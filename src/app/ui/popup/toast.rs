@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use tui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Clear, Paragraph},
+};
+
+use super::{
+    get_absolute_area, message::PopupMessageBuilder, Popup, PopupPosition,
+};
+use crate::app::{
+    helper::{split_text, CrosstermFrame},
+    theme::Theme,
+};
+
+// Number of ticks a toast is shown for unless overridden
+pub const DEFAULT_TOAST_TICKS: u16 = 30;
+const HISTORY_SIZE: usize = 50;
+const MAX_VISIBLE_TOASTS: usize = 4;
+const MAX_TOAST_WIDTH: u16 = 40;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Info => "Info",
+            Self::Success => "Success",
+            Self::Warning => "Warning",
+            Self::Error => "Error",
+        }
+    }
+
+    pub fn color(&self, theme: &Theme) -> Color {
+        match self {
+            Self::Info => theme.palette.accent,
+            Self::Success => theme.palette.success,
+            Self::Warning => theme.palette.warning,
+            Self::Error => theme.palette.error,
+        }
+    }
+}
+
+// A short message which is shown without taking focus and
+// is dismissed after a number of ticks
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message:  String,
+    pub severity: Severity,
+    pub ticks:    u16,
+}
+
+#[allow(dead_code)]
+impl Toast {
+    pub fn new<T: ToString>(message: T, severity: Severity) -> Self {
+        Self {
+            message: message.to_string(),
+            severity,
+            ticks: DEFAULT_TOAST_TICKS,
+        }
+    }
+
+    pub fn set_ticks(&mut self, ticks: u16) -> &mut Self {
+        self.ticks = ticks;
+        self
+    }
+
+    fn get_lines(&self, width: u16) -> Vec<String> {
+        // 2 for the border
+        split_text(&self.message, " ", width.saturating_sub(2) as usize)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Toasts {
+    visible:  Vec<Toast>,
+    history:  VecDeque<Toast>,
+    position: PopupPosition,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self {
+            visible:  Vec::new(),
+            history:  VecDeque::new(),
+            position: PopupPosition::BottomRight,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Toasts {
+    pub fn set_position(&mut self, position: PopupPosition) -> &mut Self {
+        self.position = position;
+        self
+    }
+
+    pub fn push(&mut self, toast: Toast) {
+        if self.history.len() == HISTORY_SIZE {
+            self.history.pop_back();
+        }
+        self.history.push_front(toast.clone());
+
+        self.visible.push(toast);
+        if self.visible.len() > MAX_VISIBLE_TOASTS {
+            self.visible.remove(0);
+        }
+    }
+
+    pub fn on_tick(&mut self) {
+        for toast in self.visible.iter_mut() {
+            toast.ticks = toast.ticks.saturating_sub(1);
+        }
+
+        self.visible.retain(|toast| toast.ticks > 0);
+    }
+
+    // Toasts are stacked with the newest closest to the
+    // edge of the screen
+    pub fn draw(&self, frame: &mut CrosstermFrame, area: Rect, theme: &Theme) {
+        if self.visible.is_empty() {
+            return;
+        }
+
+        // The oldest toasts are left out when they don't all
+        // fit so new ones are always shown
+        let width = MAX_TOAST_WIDTH.min(area.width.saturating_sub(2));
+        let mut height = 0;
+        let mut toasts = Vec::new();
+        for toast in self.visible.iter().rev() {
+            let lines = toast.get_lines(width);
+            let toast_height = lines.len() as u16 + 2;
+            if height + toast_height > area.height {
+                break;
+            }
+
+            height += toast_height;
+            toasts.push((toast, lines));
+        }
+        toasts.reverse();
+
+        let stack_area = get_absolute_area(width, height, self.position, area);
+        let mut y = stack_area.y;
+        for (toast, lines) in toasts {
+            let toast_height = lines.len() as u16 + 2;
+            let toast_area = Rect::new(stack_area.x, y, width, toast_height);
+            y += toast_height;
+
+            let color = toast.severity.color(theme);
+            let block = theme
+                .popup_block()
+                .border_style(Style::default().fg(color))
+                .title(Span::styled(
+                    toast.severity.name(),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ));
+            let paragraph = Paragraph::new(lines.join("\n"))
+                .style(theme.base_style())
+                .block(block);
+
+            frame.render_widget(Clear, toast_area);
+            frame.render_widget(paragraph, toast_area);
+        }
+    }
+
+    // Popup listing recent toasts, newest first
+    pub fn history_popup(&self) -> Popup {
+        let message = if self.history.is_empty() {
+            "No notifications.".to_string()
+        } else {
+            self.history
+                .iter()
+                .map(|toast| {
+                    format!("[{}] {}", toast.severity.name(), toast.message)
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+
+        PopupMessageBuilder::new(message)
+            .set_title(Some("Notifications"))
+            .set_modal(false)
+            .to_popup()
+    }
+}
@@ -1,5 +1,5 @@
 pub use super::{
     menu::{authentication::*, loading::*, *},
-    popup::{confirmation::*, *},
-    widget::{button::*, input::*, *},
+    popup::{confirmation::*, logs::*, palette::*, switcher::*, toast::*, *},
+    widget::{button::*, form::*, input::*, *},
 };
@@ -1,22 +1,25 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use tui::{
+    backend::Backend,
     layout::{Alignment, Rect},
-    style::{Modifier, Style},
+    style::Style,
+    terminal::Frame,
     widgets::Paragraph,
 };
 
 use super::Widget;
-use crate::app::{context::Context, helper::CrosstermFrame};
+use crate::app::{context::Context, helper::area_contains, keymap::Action};
 
 #[derive(Clone)]
 pub struct ButtonWidget {
-    pub text:           String,
-    pub submit_fn:      fn(&Context),
-    pub selected:       bool,
-    pub enabled:        bool,
-    pub inner_padding:  usize,
+    pub text: String,
+    pub submit_fn: fn(&Context),
+    pub selected: bool,
+    pub enabled: bool,
+    pub inner_padding: usize,
     pub outter_padding: usize,
-    pub alignment:      Alignment,
+    pub alignment: Alignment,
+    area: Rect,
 }
 
 #[allow(dead_code)]
@@ -67,12 +70,20 @@ impl Default for ButtonWidget {
             inner_padding:  usize::default(),
             outter_padding: usize::default(),
             alignment:      Alignment::Center,
+            area:           Rect::default(),
         }
     }
 }
 
 impl Widget for ButtonWidget {
-    fn render(&mut self, area: Rect, frame: &mut CrosstermFrame) {
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
+
         let inner_padding = " ".repeat(self.inner_padding);
         let outter_padding = " ".repeat(self.outter_padding);
 
@@ -89,7 +100,7 @@ impl Widget for ButtonWidget {
             let difference = area.width as usize - label.len();
             match self.alignment {
                 Alignment::Left => {
-                    label = label + &" ".repeat(difference);
+                    label.push_str(&" ".repeat(difference));
                 },
                 Alignment::Center => {
                     let left = if difference % 2 == 0 {
@@ -98,11 +109,11 @@ impl Widget for ButtonWidget {
                         (difference + 1) / 2
                     };
                     label = " ".repeat(left)
-                        + &label
-                        + &" ".repeat(difference - left);
+                        + label.as_str()
+                        + " ".repeat(difference - left).as_str();
                 },
                 Alignment::Right => {
-                    label = " ".repeat(difference) + &label;
+                    label = " ".repeat(difference) + label.as_str();
                 },
             }
         }
@@ -110,18 +121,33 @@ impl Widget for ButtonWidget {
         let mut style = Style::default();
         if self.selected {
             if self.enabled {
-                style = style.add_modifier(Modifier::BOLD);
+                style = ctx.theme.focus.to_style();
             }
         } else if !self.enabled {
-            style = style.add_modifier(Modifier::DIM);
+            style = style.fg(ctx.theme.palette.muted);
         };
 
         let block = Paragraph::new(label).style(style);
         frame.render_widget(block, area);
     }
 
-    fn on_key(&mut self, ctx: &Context, key: KeyEvent) {
-        if self.selected && self.enabled && KeyCode::Enter == key.code {
+    fn on_key(&mut self, _ctx: &Context, _key: KeyEvent) {}
+
+    fn on_action(&mut self, ctx: &Context, action: Action) {
+        if self.selected && self.enabled && Action::Submit == action {
+            (self.submit_fn)(ctx);
+        }
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![Action::Submit]
+    }
+
+    fn on_mouse(&mut self, ctx: &Context, event: MouseEvent) {
+        let clicked = event.kind == MouseEventKind::Down(MouseButton::Left)
+            && area_contains(self.area, event.column, event.row);
+
+        if clicked && self.enabled {
             (self.submit_fn)(ctx);
         }
     }
@@ -135,4 +161,16 @@ impl Widget for ButtonWidget {
     fn has_focus(&mut self) -> bool {
         self.selected
     }
+
+    fn is_valid(&mut self) -> bool {
+        true
+    }
+
+    fn get_value(&self) -> Option<String> {
+        None
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
 }
@@ -0,0 +1,203 @@
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::Style,
+    terminal::Frame,
+    text::{Span, Spans},
+    widgets::Paragraph,
+};
+
+use super::Widget;
+use crate::app::{context::Context, helper::area_contains, keymap::Action};
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckboxStyle {
+    // [x] Label
+    Checkbox,
+    // [ON ] Label
+    Toggle,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckboxWidget {
+    pub label:     String,
+    pub checked:   bool,
+    pub selected:  bool,
+    pub style:     CheckboxStyle,
+    pub alignment: Alignment,
+    area:          Rect,
+}
+
+#[allow(dead_code)]
+impl CheckboxWidget {
+    pub fn new<T: ToString>(label: T) -> Self {
+        Self {
+            label:     label.to_string(),
+            checked:   false,
+            selected:  false,
+            style:     CheckboxStyle::Checkbox,
+            alignment: Alignment::Left,
+            area:      Rect::default(),
+        }
+    }
+
+    pub fn set_checked(&mut self, checked: bool) -> &mut Self {
+        self.checked = checked;
+        self
+    }
+
+    pub fn set_selected(&mut self, selected: bool) -> &mut Self {
+        if selected != self.selected {
+            self.on_focus(selected);
+        }
+        self
+    }
+
+    pub fn set_style(&mut self, style: CheckboxStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    pub fn set_alignment(&mut self, alignment: Alignment) -> &mut Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn toggle(&mut self) {
+        self.checked = !self.checked;
+    }
+
+    fn get_mark(&self) -> &'static str {
+        match (self.style, self.checked) {
+            (CheckboxStyle::Checkbox, true) => "[x]",
+            (CheckboxStyle::Checkbox, false) => "[ ]",
+            (CheckboxStyle::Toggle, true) => "[ON ]",
+            (CheckboxStyle::Toggle, false) => "[OFF]",
+        }
+    }
+}
+
+impl Widget for CheckboxWidget {
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
+
+        let mark_color = if self.checked {
+            ctx.theme.palette.accent
+        } else {
+            ctx.theme.palette.muted
+        };
+        let label_style = if self.selected {
+            ctx.theme.focus.to_style()
+        } else {
+            Style::default()
+        };
+
+        let text = Spans::from(vec![
+            Span::styled(self.get_mark(), Style::default().fg(mark_color)),
+            Span::raw(" "),
+            Span::styled(self.label.clone(), label_style),
+        ]);
+        let block = Paragraph::new(text).alignment(self.alignment);
+        frame.render_widget(block, area);
+    }
+
+    fn on_key(&mut self, _ctx: &Context, _key: KeyEvent) {}
+
+    // Enter submits forms so only space toggles
+    fn on_action(&mut self, _ctx: &Context, action: Action) {
+        if self.selected && action == Action::Toggle {
+            self.toggle();
+        }
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![Action::Toggle]
+    }
+
+    fn on_mouse(&mut self, _ctx: &Context, event: MouseEvent) {
+        if event.kind == MouseEventKind::Down(MouseButton::Left)
+            && area_contains(self.area, event.column, event.row)
+        {
+            self.toggle();
+        }
+    }
+
+    fn on_tick(&mut self, _ctx: &Context) {}
+
+    fn on_focus(&mut self, arrive: bool) {
+        self.selected = arrive;
+    }
+
+    fn has_focus(&mut self) -> bool {
+        self.selected
+    }
+
+    fn is_valid(&mut self) -> bool {
+        true
+    }
+
+    fn get_value(&self) -> Option<String> {
+        Some(self.checked.to_string())
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui::widget::test::{context, lines, render};
+
+    #[test]
+    fn renders_mark_and_label() {
+        let (ctx, _receiver) = context();
+        let mut checkbox = CheckboxWidget::new("Remember");
+
+        let buffer = render(&mut checkbox, &ctx, 14, 1);
+        assert_eq!(lines(&buffer), vec!["[ ] Remember  "]);
+        assert_eq!(buffer.get(1, 0).fg, ctx.theme.palette.muted);
+
+        checkbox.set_checked(true);
+        let buffer = render(&mut checkbox, &ctx, 14, 1);
+        assert_eq!(lines(&buffer), vec!["[x] Remember  "]);
+        assert_eq!(buffer.get(1, 0).fg, ctx.theme.palette.accent);
+    }
+
+    #[test]
+    fn renders_toggle_style() {
+        let (ctx, _receiver) = context();
+        let mut checkbox = CheckboxWidget::new("Sync")
+            .set_style(CheckboxStyle::Toggle)
+            .to_owned();
+
+        let buffer = render(&mut checkbox, &ctx, 10, 1);
+        assert_eq!(lines(&buffer), vec!["[OFF] Sync"]);
+
+        checkbox.set_checked(true);
+        let buffer = render(&mut checkbox, &ctx, 10, 1);
+        assert_eq!(lines(&buffer), vec!["[ON ] Sync"]);
+    }
+
+    #[test]
+    fn toggles_when_selected() {
+        let (ctx, _receiver) = context();
+        let mut checkbox = CheckboxWidget::new("Remember");
+
+        checkbox.on_action(&ctx, Action::Toggle);
+        assert!(!checkbox.checked);
+
+        checkbox.set_selected(true);
+        checkbox.on_action(&ctx, Action::Toggle);
+        let buffer = render(&mut checkbox, &ctx, 14, 1);
+        assert_eq!(lines(&buffer), vec!["[x] Remember  "]);
+    }
+}
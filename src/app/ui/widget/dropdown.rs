@@ -0,0 +1,299 @@
+use std::sync::{Arc, Mutex};
+
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Direction, Rect},
+    style::Style,
+    terminal::Frame,
+    text::{Span, Spans},
+    widgets::Paragraph,
+};
+
+use super::{list::ListWidget, Widget};
+use crate::app::{
+    context::{Context, Notification},
+    event::Event,
+    helper::{area_contains, split_rect, CrosstermFrame},
+    keymap::Action,
+    ui::prelude::{Menu, Popup, PopupArea, PopupId},
+};
+
+// Maximum number of options shown at once when open
+const MAX_DROPDOWN_HEIGHT: u16 = 8;
+
+// Select which opens a list of options below itself
+#[derive(Debug, Clone)]
+pub struct DropdownWidget {
+    pub label: String,
+    pub label_align: Alignment,
+    pub split_percentage: u16,
+    pub options: Vec<String>,
+    pub selected: bool,
+    // Shared with the popup so that it can set the option
+    // once it's chosen
+    chosen: Arc<Mutex<usize>>,
+    area: Rect,
+    control_area: Rect,
+}
+
+#[allow(dead_code)]
+impl DropdownWidget {
+    pub fn new<T: ToString>(label: T, options: Vec<String>) -> Self {
+        Self {
+            label: label.to_string(),
+            label_align: Alignment::Left,
+            split_percentage: 40,
+            options,
+            selected: false,
+            chosen: Arc::new(Mutex::new(0)),
+            area: Rect::default(),
+            control_area: Rect::default(),
+        }
+    }
+
+    pub fn set_chosen(&mut self, chosen: usize) -> &mut Self {
+        *self.chosen.lock().unwrap() =
+            chosen.min(self.options.len().saturating_sub(1));
+        self
+    }
+
+    pub fn set_selected(&mut self, selected: bool) -> &mut Self {
+        if selected != self.selected {
+            self.on_focus(selected);
+        }
+        self
+    }
+
+    pub fn set_alignment(&mut self, alignment: Alignment) -> &mut Self {
+        self.label_align = alignment;
+        self
+    }
+
+    pub fn set_split(&mut self, percentage: u16) -> &mut Self {
+        self.split_percentage = percentage;
+        self
+    }
+
+    pub fn get_chosen(&self) -> Option<&String> {
+        self.options.get(*self.chosen.lock().unwrap())
+    }
+
+    fn open(&self, ctx: &Context) {
+        if self.options.is_empty() {
+            return;
+        }
+
+        let mut list = ListWidget::new(self.options.clone())
+            .set_selected(true)
+            .to_owned();
+        list.select(*self.chosen.lock().unwrap());
+
+        let height = (self.options.len() as u16).min(MAX_DROPDOWN_HEIGHT);
+        let id = PopupId::next();
+        let menu = DropdownMenu {
+            id,
+            list,
+            chosen: self.chosen.clone(),
+        };
+        let popup = Popup::new(
+            id,
+            Box::new(menu),
+            PopupArea::Anchored(self.control_area, height),
+        );
+
+        ctx.send_notification(Notification::ShowPopup(popup));
+    }
+}
+
+impl Widget for DropdownWidget {
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
+
+        let split =
+            split_rect(self.split_percentage, Direction::Horizontal, area);
+        self.control_area = split[1];
+
+        let label_style = if self.selected {
+            ctx.theme.focus.to_style()
+        } else {
+            Style::default()
+        };
+        let label =
+            Paragraph::new(Span::styled(self.label.clone(), label_style))
+                .alignment(self.label_align);
+        frame.render_widget(label, split[0]);
+
+        // Pad the value so the arrow is always at the end
+        let width = (split[1].width as usize).saturating_sub(2);
+        let value = self.get_chosen().cloned().unwrap_or_default();
+        let color = if self.selected {
+            ctx.theme.palette.accent
+        } else {
+            ctx.theme.palette.muted
+        };
+        let text = Spans::from(vec![
+            Span::raw(format!("{:1$.1$}", value, width)),
+            Span::styled(" ▾", Style::default().fg(color)),
+        ]);
+        frame.render_widget(Paragraph::new(text), split[1]);
+    }
+
+    fn on_key(&mut self, _ctx: &Context, _key: KeyEvent) {}
+
+    fn on_action(&mut self, ctx: &Context, action: Action) {
+        if self.selected && action == Action::Toggle {
+            self.open(ctx);
+        }
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![Action::Toggle]
+    }
+
+    fn on_mouse(&mut self, ctx: &Context, event: MouseEvent) {
+        if event.kind == MouseEventKind::Down(MouseButton::Left)
+            && area_contains(self.control_area, event.column, event.row)
+        {
+            self.open(ctx);
+        }
+    }
+
+    fn on_tick(&mut self, _ctx: &Context) {}
+
+    fn on_focus(&mut self, arrive: bool) {
+        self.selected = arrive;
+    }
+
+    fn has_focus(&mut self) -> bool {
+        self.selected
+    }
+
+    fn is_valid(&mut self) -> bool {
+        true
+    }
+
+    fn get_value(&self) -> Option<String> {
+        self.get_chosen().cloned()
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
+}
+
+struct DropdownMenu {
+    id:     PopupId,
+    list:   ListWidget<String>,
+    chosen: Arc<Mutex<usize>>,
+}
+
+impl DropdownMenu {
+    fn choose(&mut self, ctx: &Context) {
+        *self.chosen.lock().unwrap() = self.list.selected;
+
+        ctx.send_notification(Notification::HidePopup(self.id));
+    }
+}
+
+impl Menu for DropdownMenu {
+    fn on_event(&mut self, event: Event, ctx: &Context) {
+        match event {
+            Event::Tick => self.list.on_tick(ctx),
+            Event::Key(key) => self.list.on_key(ctx, key),
+            Event::Action(action) => match action {
+                Action::FocusPrevious => {
+                    self.list.on_action(ctx, Action::MoveUp);
+                },
+                Action::FocusNext => {
+                    self.list.on_action(ctx, Action::MoveDown);
+                },
+                Action::Submit => self.choose(ctx),
+                Action::ClosePopup => {
+                    ctx.send_notification(Notification::HidePopup(self.id));
+                },
+                _ => self.list.on_action(ctx, action),
+            },
+            Event::Mouse(event) => {
+                self.list.on_mouse(ctx, event);
+
+                let area = self.list.get_area();
+                if event.kind == MouseEventKind::Down(MouseButton::Left)
+                    && area_contains(area, event.column, event.row)
+                {
+                    self.choose(ctx);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn get_actions(&mut self, _ctx: &Context) -> Vec<Action> {
+        let mut actions = vec![
+            Action::FocusPrevious,
+            Action::FocusNext,
+            Action::Submit,
+            Action::ClosePopup,
+        ];
+        actions.append(&mut self.list.get_actions());
+
+        actions
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut CrosstermFrame,
+        max_size: Rect,
+        ctx: &Context,
+    ) {
+        self.list.render(max_size, frame, ctx);
+    }
+
+    fn get_minimum_size(&mut self) -> (u16, u16) {
+        (0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui::widget::test::{context, lines, render};
+
+    fn options() -> Vec<String> {
+        vec!["Small".to_string(), "Large".to_string()]
+    }
+
+    #[test]
+    fn renders_chosen_option() {
+        let (ctx, _receiver) = context();
+        let mut dropdown = DropdownWidget::new("Size", options());
+
+        let buffer = render(&mut dropdown, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Size        Small            ▾"]);
+
+        dropdown.set_chosen(1);
+        let buffer = render(&mut dropdown, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Size        Large            ▾"]);
+    }
+
+    #[test]
+    fn opens_popup_when_selected() {
+        let (ctx, receiver) = context();
+        let mut dropdown = DropdownWidget::new("Size", options());
+
+        dropdown.on_action(&ctx, Action::Toggle);
+        assert!(receiver.try_recv().is_err());
+
+        dropdown.set_selected(true);
+        dropdown.on_action(&ctx, Action::Toggle);
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Notification::ShowPopup(_))
+        ));
+    }
+}
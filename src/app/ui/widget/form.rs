@@ -0,0 +1,308 @@
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use tui::layout::Rect;
+
+use super::{CrosstermWidget, Widget};
+use crate::app::{
+    context::Context,
+    helper::{area_contains, CrosstermFrame},
+    keymap::Action,
+};
+
+pub enum FormResult {
+    // Every enabled field passed validation
+    Submit,
+    // Messages for each field which failed validation
+    Invalid(Vec<String>),
+    None,
+}
+
+struct FormField {
+    name:    String,
+    widget:  Box<dyn CrosstermWidget + Send>,
+    enabled: bool,
+    error:   Option<String>,
+    submit:  bool,
+}
+
+// Container which handles focus traversal, validation and
+// submission for a group of widgets
+#[derive(Default)]
+pub struct Form {
+    fields:      Vec<FormField>,
+    focus_index: usize,
+}
+
+#[allow(dead_code)]
+impl Form {
+    // Fields are focused in the order they're added, the
+    // first enabled field gets focus
+    pub fn add_field<T, W>(&mut self, name: T, mut widget: W) -> &mut Self
+    where
+        T: ToString,
+        W: Widget + Send + 'static, {
+        widget.on_focus(self.fields.is_empty());
+        self.fields.push(FormField {
+            name:    name.to_string(),
+            widget:  Box::new(widget),
+            enabled: true,
+            error:   None,
+            submit:  false,
+        });
+        self
+    }
+
+    // Message shown in the validation summary when the
+    // field is invalid
+    pub fn set_error<T: ToString>(
+        &mut self,
+        name: &str,
+        error: T,
+    ) -> &mut Self {
+        if let Some(field) = self.get_field(name) {
+            field.error = Some(error.to_string());
+        }
+        self
+    }
+
+    // Clicking a submit field submits the form
+    pub fn set_submit(&mut self, name: &str, submit: bool) -> &mut Self {
+        if let Some(field) = self.get_field(name) {
+            field.submit = submit;
+        }
+        self
+    }
+
+    // Disabled fields are skipped when moving focus and
+    // aren't validated
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> &mut Self {
+        if let Some(field) = self.get_field(name) {
+            field.enabled = enabled;
+        }
+
+        if !self.is_enabled(self.focus_index) {
+            if let Some(idx) = self.next_enabled(self.focus_index, true) {
+                self.set_focus(idx);
+            }
+        }
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get_focus(&self) -> usize {
+        self.focus_index
+    }
+
+    pub fn set_focus(&mut self, focus_index: usize) {
+        self.focus_index = focus_index;
+
+        for (idx, field) in self.fields.iter_mut().enumerate() {
+            let focused = idx == focus_index;
+            if field.widget.has_focus() != focused {
+                field.widget.on_focus(focused);
+            }
+        }
+    }
+
+    pub fn get_value(&self, name: &str) -> Option<String> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .and_then(|field| field.widget.get_value())
+    }
+
+    pub fn focus_next(&mut self) {
+        if let Some(idx) = self.next_enabled(self.focus_index + 1, true) {
+            self.set_focus(idx);
+        }
+    }
+
+    pub fn focus_previous(&mut self) {
+        let start = self.focus_index + self.fields.len().max(1) - 1;
+        if let Some(idx) = self.next_enabled(start, false) {
+            self.set_focus(idx);
+        }
+    }
+
+    pub fn focus_first(&mut self) {
+        if let Some(idx) = self.next_enabled(0, true) {
+            self.set_focus(idx);
+        }
+    }
+
+    pub fn focus_last(&mut self) {
+        let start = self.fields.len().max(1) - 1;
+        if let Some(idx) = self.next_enabled(start, false) {
+            self.set_focus(idx);
+        }
+    }
+
+    // Errors for every enabled field which failed validation
+    pub fn validate(&mut self) -> Vec<String> {
+        self.fields
+            .iter_mut()
+            .filter(|field| field.enabled)
+            .filter_map(|field| {
+                if field.widget.is_valid() {
+                    None
+                } else {
+                    Some(field.error.clone().unwrap_or_else(|| {
+                        format!("{} is invalid.", field.name)
+                    }))
+                }
+            })
+            .collect()
+    }
+
+    pub fn submit(&mut self, ctx: &Context) -> FormResult {
+        let errors = self.validate();
+        if !errors.is_empty() {
+            return FormResult::Invalid(errors);
+        }
+
+        if let Some(field) = self.get_focused() {
+            field.widget.on_action(ctx, Action::Submit);
+        }
+
+        FormResult::Submit
+    }
+
+    // Actions which the form and its focused field respond
+    // to, menus should include these in their own actions
+    pub fn get_actions(&self) -> Vec<Action> {
+        let mut actions = vec![
+            Action::FocusPrevious,
+            Action::FocusNext,
+            Action::Submit,
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::JumpTop,
+            Action::JumpBottom,
+        ];
+
+        let focused = self
+            .fields
+            .get(self.focus_index)
+            .filter(|field| field.enabled);
+        if let Some(field) = focused {
+            for action in field.widget.get_actions() {
+                if !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+        }
+
+        actions
+    }
+
+    pub fn on_action(&mut self, ctx: &Context, action: Action) -> FormResult {
+        match action {
+            Action::FocusPrevious | Action::MoveUp => self.focus_previous(),
+            Action::FocusNext | Action::MoveDown => self.focus_next(),
+            Action::JumpTop => self.focus_first(),
+            Action::JumpBottom => self.focus_last(),
+            Action::Submit => return self.submit(ctx),
+            _ => {
+                if let Some(field) = self.get_focused() {
+                    field.widget.on_action(ctx, action);
+                }
+            },
+        }
+
+        FormResult::None
+    }
+
+    pub fn on_key(&mut self, ctx: &Context, key: KeyEvent) {
+        if let Some(field) = self.get_focused() {
+            field.widget.on_key(ctx, key);
+        }
+    }
+
+    pub fn on_mouse(&mut self, ctx: &Context, event: MouseEvent) -> FormResult {
+        let clicked = if event.kind == MouseEventKind::Down(MouseButton::Left) {
+            self.fields.iter().position(|field| {
+                field.enabled
+                    && area_contains(
+                        field.widget.get_area(),
+                        event.column,
+                        event.row,
+                    )
+            })
+        } else {
+            None
+        };
+
+        if let Some(idx) = clicked {
+            self.set_focus(idx);
+        }
+
+        for (idx, field) in self.fields.iter_mut().enumerate() {
+            // Submit fields are handled below so that they're
+            // only submitted once validated
+            if field.enabled && !(field.submit && clicked == Some(idx)) {
+                field.widget.on_mouse(ctx, event);
+            }
+        }
+
+        match clicked {
+            Some(idx) if self.fields[idx].submit => self.submit(ctx),
+            _ => FormResult::None,
+        }
+    }
+
+    pub fn on_tick(&mut self, ctx: &Context) {
+        for field in self.fields.iter_mut() {
+            field.widget.on_tick(ctx);
+        }
+    }
+
+    // Render each field in the matching area, any fields
+    // without an area aren't drawn
+    pub fn render(
+        &mut self,
+        areas: &[Rect],
+        frame: &mut CrosstermFrame,
+        ctx: &Context,
+    ) {
+        for (field, area) in self.fields.iter_mut().zip(areas) {
+            field.widget.render_crossterm(*area, frame, ctx);
+        }
+    }
+
+    fn get_field(&mut self, name: &str) -> Option<&mut FormField> {
+        self.fields.iter_mut().find(|field| field.name == name)
+    }
+
+    fn get_focused(&mut self) -> Option<&mut FormField> {
+        let idx = self.focus_index;
+        self.fields.get_mut(idx).filter(|field| field.enabled)
+    }
+
+    fn is_enabled(&self, idx: usize) -> bool {
+        self.fields
+            .get(idx)
+            .map(|field| field.enabled)
+            .unwrap_or(false)
+    }
+
+    // Find the next enabled field starting at start and
+    // wrapping around the ends of the form
+    fn next_enabled(&self, start: usize, forwards: bool) -> Option<usize> {
+        let len = self.fields.len();
+        (0..len)
+            .map(|offset| {
+                if forwards {
+                    (start + offset) % len
+                } else {
+                    (start + len - offset % len) % len
+                }
+            })
+            .find(|idx| self.is_enabled(*idx))
+    }
+}
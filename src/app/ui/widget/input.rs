@@ -1,14 +1,19 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{
+    KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind,
+};
 use tui::{
+    backend::Backend,
     layout::{Alignment, Direction, Rect},
-    style::{Color, Style},
+    style::Style,
+    terminal::Frame,
     widgets::Paragraph,
 };
 
 use super::Widget;
 use crate::app::{
     context::Context,
-    helper::{split_rect, CrosstermFrame},
+    helper::{area_contains, split_rect},
+    keymap::Action,
 };
 
 const CURSOR_BLINK_TICKS: u8 = 6;
@@ -35,6 +40,7 @@ pub struct InputWidget {
     pub cursor_pos: usize,
     scroll_pos:     usize,
     tick_count:     u8,
+    area:           Rect,
 }
 
 #[allow(dead_code)]
@@ -70,17 +76,16 @@ impl InputWidget {
         self.cursor_pos = pos;
         self
     }
-
-    pub fn is_valid(&mut self) -> bool {
-        match self.validation {
-            ValidationType::Manual(value) => value,
-            ValidationType::Functional(func) => func(self.value.clone()),
-        }
-    }
 }
 
 impl Widget for InputWidget {
-    fn render(&mut self, area: Rect, frame: &mut CrosstermFrame) {
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
         let max_len = area.width as usize;
 
         let mut value_text = if self.secret {
@@ -129,10 +134,12 @@ impl Widget for InputWidget {
             }
         }
 
-        let block =
-            Paragraph::new(text).style(Style::default().fg(
-                if self.is_valid() { Color::Indexed(8) } else { Color::Red },
-            ));
+        let color = if self.is_valid() {
+            ctx.theme.palette.muted
+        } else {
+            ctx.theme.palette.error
+        };
+        let block = Paragraph::new(text).style(Style::default().fg(color));
 
         frame.render_widget(block, area);
     }
@@ -182,6 +189,22 @@ impl Widget for InputWidget {
         }
     }
 
+    fn on_action(&mut self, _ctx: &Context, _action: Action) {}
+
+    fn get_actions(&self) -> Vec<Action> {
+        Vec::new()
+    }
+
+    fn on_mouse(&mut self, _ctx: &Context, event: MouseEvent) {
+        if event.kind == MouseEventKind::Down(MouseButton::Left)
+            && area_contains(self.area, event.column, event.row)
+        {
+            let offset = (event.column - self.area.x) as usize;
+            self.cursor_pos = (self.scroll_pos + offset).min(self.value.len());
+            self.tick_count = 0;
+        }
+    }
+
     fn on_tick(&mut self, _ctx: &Context) {
         if self.selected {
             self.tick_count += 1;
@@ -198,14 +221,30 @@ impl Widget for InputWidget {
     fn has_focus(&mut self) -> bool {
         self.selected
     }
+
+    fn is_valid(&mut self) -> bool {
+        match self.validation {
+            ValidationType::Manual(value) => value,
+            ValidationType::Functional(func) => func(self.value.clone()),
+        }
+    }
+
+    fn get_value(&self) -> Option<String> {
+        Some(self.value.clone())
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct LabeledInputWidget {
-    pub label:            String,
-    pub label_align:      Alignment,
+    pub label: String,
+    pub label_align: Alignment,
     pub split_percentage: u16,
-    pub input:            InputWidget,
+    pub input: InputWidget,
+    area: Rect,
 }
 
 #[allow(dead_code)]
@@ -216,6 +255,7 @@ impl LabeledInputWidget {
             label_align:      Alignment::Left,
             split_percentage: 40,
             input:            InputWidget::default(),
+            area:             Rect::default(),
         }
     }
 
@@ -264,14 +304,33 @@ impl Widget for LabeledInputWidget {
         self.input.on_key(ctx, key);
     }
 
-    fn render(&mut self, area: Rect, frame: &mut CrosstermFrame) {
+    fn on_action(&mut self, ctx: &Context, action: Action) {
+        self.input.on_action(ctx, action);
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        self.input.get_actions()
+    }
+
+    fn on_mouse(&mut self, ctx: &Context, event: MouseEvent) {
+        self.input.on_mouse(ctx, event);
+    }
+
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
+
         let split =
             split_rect(self.split_percentage, Direction::Horizontal, area);
 
         let label =
             Paragraph::new(self.label.clone()).alignment(self.label_align);
         frame.render_widget(label, split[0]);
-        self.input.render(split[1], frame);
+        self.input.render(split[1], frame, ctx);
     }
 
     fn on_focus(&mut self, arrive: bool) {
@@ -281,4 +340,16 @@ impl Widget for LabeledInputWidget {
     fn has_focus(&mut self) -> bool {
         self.input.selected
     }
+
+    fn is_valid(&mut self) -> bool {
+        self.input.is_valid()
+    }
+
+    fn get_value(&self) -> Option<String> {
+        self.input.get_value()
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
 }
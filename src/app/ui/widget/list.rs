@@ -0,0 +1,439 @@
+use std::collections::BTreeSet;
+
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    terminal::Frame,
+    text::{Span, Spans},
+    widgets::Paragraph,
+};
+
+use super::Widget;
+use crate::app::{context::Context, helper::area_contains, keymap::Action};
+
+// Ticks without typing before the type to jump search is
+// cleared
+const SEARCH_RESET_TICKS: u8 = 10;
+const SCROLL_LINES: usize = 3;
+
+// List which only formats and renders the rows which are
+// visible so that it stays fast with thousands of items
+#[derive(Debug, Clone)]
+pub struct ListWidget<T> {
+    pub items:        Vec<T>,
+    pub format_fn:    fn(&T) -> String,
+    pub selected:     usize,
+    pub multi_select: bool,
+    pub focused:      bool,
+    marked:           BTreeSet<usize>,
+    offset:           usize,
+    search:           String,
+    search_ticks:     u8,
+    area:             Rect,
+}
+
+#[allow(dead_code)]
+impl<T: ToString> ListWidget<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        Self::with_format(items, |item| item.to_string())
+    }
+}
+
+#[allow(dead_code)]
+impl<T> ListWidget<T> {
+    pub fn with_format(items: Vec<T>, format_fn: fn(&T) -> String) -> Self {
+        Self {
+            items,
+            format_fn,
+            selected: 0,
+            multi_select: false,
+            focused: false,
+            marked: BTreeSet::new(),
+            offset: 0,
+            search: String::default(),
+            search_ticks: 0,
+            area: Rect::default(),
+        }
+    }
+
+    // Keeps the selection where possible, marked items are
+    // cleared as their indices may no longer be valid
+    pub fn set_items(&mut self, items: Vec<T>) -> &mut Self {
+        self.items = items;
+        self.marked.clear();
+        self.select(self.selected);
+        self
+    }
+
+    pub fn set_multi_select(&mut self, multi_select: bool) -> &mut Self {
+        self.multi_select = multi_select;
+        if !multi_select {
+            self.marked.clear();
+        }
+        self
+    }
+
+    pub fn set_selected(&mut self, selected: bool) -> &mut Self {
+        if selected != self.focused {
+            self.on_focus(selected);
+        }
+        self
+    }
+
+    pub fn get_selected(&self) -> Option<&T> {
+        self.items.get(self.selected)
+    }
+
+    pub fn get_marked(&self) -> Vec<&T> {
+        self.marked
+            .iter()
+            .filter_map(|idx| self.items.get(*idx))
+            .collect()
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        self.selected = idx.min(self.items.len().saturating_sub(1));
+    }
+
+    pub fn toggle_mark(&mut self, idx: usize) {
+        if !self.multi_select || idx >= self.items.len() {
+            return;
+        }
+
+        if !self.marked.remove(&idx) {
+            self.marked.insert(idx);
+        }
+    }
+
+    fn move_up(&mut self, amount: usize) {
+        self.select(self.selected.saturating_sub(amount));
+    }
+
+    fn move_down(&mut self, amount: usize) {
+        self.select(self.selected.saturating_add(amount));
+    }
+
+    fn page_size(&self) -> usize {
+        (self.area.height as usize).max(1)
+    }
+
+    // Jump to the first item starting with the typed text,
+    // the current item is checked first so that typing
+    // more letters doesn't move off of it
+    fn jump_to_search(&mut self) {
+        let search = self.search.to_lowercase();
+        let len = self.items.len();
+        let found =
+            (0..len)
+                .map(|offset| (self.selected + offset) % len)
+                .find(|idx| {
+                    (self.format_fn)(&self.items[*idx])
+                        .to_lowercase()
+                        .starts_with(&search)
+                });
+
+        if let Some(idx) = found {
+            self.select(idx);
+        }
+    }
+
+    // Keep the selected item on screen
+    fn update_offset(&mut self, height: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if height > 0 && self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+
+        let max_offset = self.items.len().saturating_sub(height);
+        self.offset = self.offset.min(max_offset);
+    }
+
+    fn render_scrollbar<B: Backend>(&self, area: Rect, frame: &mut Frame<B>) {
+        let height = area.height as usize;
+        let len = self.items.len();
+
+        let thumb_size = (height * height / len).max(1);
+        let max_offset = len - height;
+        let thumb_start = (height - thumb_size) * self.offset / max_offset;
+
+        let lines = (0..height)
+            .map(|idx| {
+                let thumb =
+                    idx >= thumb_start && idx < thumb_start + thumb_size;
+                Spans::from(if thumb { "█" } else { "│" })
+            })
+            .collect::<Vec<Spans>>();
+
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+}
+
+impl<T> Widget for ListWidget<T> {
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
+
+        let height = area.height as usize;
+        self.update_offset(height);
+
+        let has_scrollbar =
+            height > 0 && self.items.len() > height && area.width > 1;
+        let list_area = if has_scrollbar {
+            Rect::new(area.x, area.y, area.width - 1, area.height)
+        } else {
+            area
+        };
+
+        let end = (self.offset + height).min(self.items.len());
+        let lines = (self.offset..end)
+            .map(|idx| {
+                let mut text = (self.format_fn)(&self.items[idx]);
+                if self.multi_select {
+                    let mark =
+                        if self.marked.contains(&idx) { "x" } else { " " };
+                    text = format!("[{}] {}", mark, text);
+                }
+
+                let style = if idx == self.selected {
+                    if self.focused {
+                        ctx.theme.selection.to_style()
+                    } else {
+                        ctx.theme
+                            .selection
+                            .to_style()
+                            .fg(ctx.theme.palette.muted)
+                    }
+                } else {
+                    Style::default()
+                };
+
+                // Pad so the selection covers the whole row
+                let width = list_area.width as usize;
+                Spans::from(Span::styled(format!("{:1$}", text, width), style))
+            })
+            .collect::<Vec<Spans>>();
+
+        frame.render_widget(Paragraph::new(lines), list_area);
+
+        if has_scrollbar {
+            let scrollbar_area =
+                Rect::new(area.right() - 1, area.y, 1, area.height);
+            self.render_scrollbar(scrollbar_area, frame);
+        }
+    }
+
+    fn on_key(&mut self, _ctx: &Context, key: KeyEvent) {
+        if !self.focused {
+            return;
+        }
+
+        if let KeyCode::Char(c) = key.code {
+            self.search.push(c);
+            self.search_ticks = 0;
+            self.jump_to_search();
+        }
+    }
+
+    fn on_action(&mut self, _ctx: &Context, action: Action) {
+        if !self.focused {
+            return;
+        }
+
+        match action {
+            Action::MoveUp => self.move_up(1),
+            Action::MoveDown => self.move_down(1),
+            Action::PageUp => self.move_up(self.page_size()),
+            Action::PageDown => self.move_down(self.page_size()),
+            Action::JumpTop | Action::First => self.select(0),
+            Action::JumpBottom | Action::Last => {
+                self.select(self.items.len());
+            },
+            Action::Toggle => self.toggle_mark(self.selected),
+            _ => {},
+        }
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        if !self.focused {
+            return Vec::new();
+        }
+
+        let mut actions = vec![
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::PageUp,
+            Action::PageDown,
+            Action::JumpTop,
+            Action::JumpBottom,
+            Action::First,
+            Action::Last,
+        ];
+        if self.multi_select {
+            actions.push(Action::Toggle);
+        }
+
+        actions
+    }
+
+    fn on_mouse(&mut self, _ctx: &Context, event: MouseEvent) {
+        if !area_contains(self.area, event.column, event.row) {
+            return;
+        }
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let idx = self.offset + (event.row - self.area.y) as usize;
+                if idx >= self.items.len() {
+                    return;
+                }
+
+                self.select(idx);
+                if event.modifiers.contains(KeyModifiers::CONTROL) {
+                    self.toggle_mark(idx);
+                }
+            },
+            // Drag the selection along with the view as the
+            // view is scrolled back to the selection when
+            // rendering
+            MouseEventKind::ScrollUp => {
+                self.offset = self.offset.saturating_sub(SCROLL_LINES);
+                self.selected = self
+                    .selected
+                    .min(self.offset + self.page_size() - 1)
+                    .min(self.items.len().saturating_sub(1));
+            },
+            MouseEventKind::ScrollDown => {
+                let max_offset =
+                    self.items.len().saturating_sub(self.page_size());
+                self.offset = (self.offset + SCROLL_LINES).min(max_offset);
+                self.selected = self.selected.max(self.offset);
+            },
+            _ => {},
+        }
+    }
+
+    fn on_tick(&mut self, _ctx: &Context) {
+        if self.search.is_empty() {
+            return;
+        }
+
+        self.search_ticks += 1;
+        if self.search_ticks >= SEARCH_RESET_TICKS {
+            self.search.clear();
+            self.search_ticks = 0;
+        }
+    }
+
+    fn on_focus(&mut self, arrive: bool) {
+        self.focused = arrive;
+    }
+
+    fn has_focus(&mut self) -> bool {
+        self.focused
+    }
+
+    fn is_valid(&mut self) -> bool {
+        true
+    }
+
+    fn get_value(&self) -> Option<String> {
+        self.get_selected().map(self.format_fn)
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui::widget::test::{context, lines, render};
+
+    fn list(len: usize) -> ListWidget<String> {
+        let items = (0..len).map(|idx| format!("Item {}", idx)).collect();
+        ListWidget::new(items).set_selected(true).to_owned()
+    }
+
+    #[test]
+    fn renders_only_visible_rows() {
+        let (ctx, _receiver) = context();
+        let mut list = list(3);
+
+        // Everything fits so there's no scrollbar
+        let buffer = render(&mut list, &ctx, 8, 4);
+        assert_eq!(lines(&buffer), vec![
+            "Item 0  ", "Item 1  ", "Item 2  ", "        "
+        ]);
+    }
+
+    #[test]
+    fn scrolls_to_keep_selection_visible() {
+        let (ctx, _receiver) = context();
+        let mut list = list(10);
+
+        let buffer = render(&mut list, &ctx, 8, 4);
+        assert_eq!(lines(&buffer), vec![
+            "Item 0 █",
+            "Item 1 │",
+            "Item 2 │",
+            "Item 3 │"
+        ]);
+
+        list.select(6);
+        let buffer = render(&mut list, &ctx, 8, 4);
+        assert_eq!(lines(&buffer), vec![
+            "Item 3 │",
+            "Item 4 █",
+            "Item 5 │",
+            "Item 6 │"
+        ]);
+
+        // Moving back above the view scrolls up to it
+        list.select(1);
+        let buffer = render(&mut list, &ctx, 8, 4);
+        assert_eq!(lines(&buffer)[0], "Item 1 █");
+    }
+
+    #[test]
+    fn scrollbar_reaches_bottom_at_end() {
+        let (ctx, _receiver) = context();
+        let mut list = list(10);
+
+        list.on_action(&ctx, Action::JumpBottom);
+        let buffer = render(&mut list, &ctx, 8, 4);
+        assert_eq!(lines(&buffer), vec![
+            "Item 6 │",
+            "Item 7 │",
+            "Item 8 │",
+            "Item 9 █"
+        ]);
+    }
+
+    #[test]
+    fn mouse_scroll_drags_selection() {
+        let (ctx, _receiver) = context();
+        let mut list = list(10);
+        render(&mut list, &ctx, 8, 4);
+
+        list.on_mouse(&ctx, MouseEvent {
+            kind:      MouseEventKind::ScrollDown,
+            column:    0,
+            row:       0,
+            modifiers: KeyModifiers::NONE,
+        });
+        assert_eq!(list.selected, 3);
+
+        let buffer = render(&mut list, &ctx, 8, 4);
+        assert_eq!(lines(&buffer)[0], "Item 3 │");
+    }
+}
@@ -1,40 +1,109 @@
-use std::ops::DerefMut;
+use crossterm::event::{KeyEvent, MouseEvent};
+use tui::{backend::Backend, layout::Rect, terminal::Frame};
 
-use crossterm::event::KeyEvent;
-use tui::layout::Rect;
-
-use crate::app::{context::Context, helper::CrosstermFrame};
+use crate::app::{context::Context, helper::CrosstermFrame, keymap::Action};
 
 pub mod button;
+pub mod checkbox;
+pub mod dropdown;
+pub mod form;
 pub mod input;
+pub mod list;
+pub mod radio;
+pub mod slider;
 
 pub trait Widget {
     fn on_key(&mut self, ctx: &Context, key: KeyEvent);
+    fn on_action(&mut self, ctx: &Context, action: Action);
+    // Actions the widget responds to while focused, so that
+    // keys such as space are only bound when they're used
+    fn get_actions(&self) -> Vec<Action>;
+    fn on_mouse(&mut self, ctx: &Context, event: MouseEvent);
     fn on_tick(&mut self, ctx: &Context);
 
-    fn render(&mut self, area: Rect, frame: &mut CrosstermFrame);
+    // Generic over the backend so that widgets can be drawn
+    // to a test backend
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) where
+        Self: Sized;
     fn has_focus(&mut self) -> bool;
     fn on_focus(&mut self, arrive: bool);
+    fn is_valid(&mut self) -> bool;
+    // The value entered into the widget, used by forms
+    fn get_value(&self) -> Option<String>;
+    // The area the widget was last rendered in, used for
+    // hit testing mouse events
+    fn get_area(&self) -> Rect;
 }
 
-impl Widget for Box<dyn Widget> {
-    fn render(&mut self, area: Rect, frame: &mut CrosstermFrame) {
-        self.deref_mut().render(area, frame)
-    }
+// Widgets stored as trait objects can't be rendered through
+// the generic method, so they're drawn to the terminal here
+pub trait CrosstermWidget: Widget {
+    fn render_crossterm(
+        &mut self,
+        area: Rect,
+        frame: &mut CrosstermFrame,
+        ctx: &Context,
+    );
+}
 
-    fn on_key(&mut self, ctx: &Context, key: KeyEvent) {
-        self.deref_mut().on_key(ctx, key)
+impl<W: Widget> CrosstermWidget for W {
+    fn render_crossterm(
+        &mut self,
+        area: Rect,
+        frame: &mut CrosstermFrame,
+        ctx: &Context,
+    ) {
+        self.render(area, frame, ctx);
     }
+}
 
-    fn on_tick(&mut self, ctx: &Context) {
-        self.deref_mut().on_tick(ctx)
+#[cfg(test)]
+pub mod test {
+    use std::sync::mpsc::Receiver;
+
+    use tui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+    use super::Widget;
+    use crate::{
+        app::context::{Context, Notification},
+        config::Config,
+    };
+
+    // Context with the default config and theme, the receiver
+    // has to be kept so notifications can be sent
+    pub fn context() -> (Context, Receiver<Notification>) {
+        Context::new(Config::default(), Vec::new())
     }
 
-    fn has_focus(&mut self) -> bool {
-        self.deref_mut().has_focus()
+    pub fn render<W: Widget>(
+        widget: &mut W,
+        ctx: &Context,
+        width: u16,
+        height: u16,
+    ) -> Buffer {
+        let backend = TestBackend::new(width, height);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| widget.render(frame.size(), frame, ctx))
+            .unwrap();
+
+        terminal.backend().buffer().clone()
     }
 
-    fn on_focus(&mut self, arrive: bool) {
-        self.deref_mut().on_focus(arrive)
+    // Text of each line without any styling
+    pub fn lines(buffer: &Buffer) -> Vec<String> {
+        let area = buffer.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect()
+            })
+            .collect()
     }
 }
@@ -0,0 +1,221 @@
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Direction, Rect},
+    style::Style,
+    terminal::Frame,
+    text::{Span, Spans},
+    widgets::Paragraph,
+};
+
+use super::Widget;
+use crate::app::{
+    context::Context,
+    helper::{area_contains, split_rect},
+    keymap::Action,
+};
+
+// Spacing between each option
+const OPTION_GAP: u16 = 2;
+
+// Row of options where only one can be chosen
+#[derive(Debug, Clone)]
+pub struct RadioGroupWidget {
+    pub label: String,
+    pub label_align: Alignment,
+    pub split_percentage: u16,
+    pub options: Vec<String>,
+    pub chosen: usize,
+    pub selected: bool,
+    area: Rect,
+    // Column ranges of each option from the last render
+    option_areas: Vec<(u16, u16)>,
+}
+
+#[allow(dead_code)]
+impl RadioGroupWidget {
+    pub fn new<T: ToString>(label: T, options: Vec<String>) -> Self {
+        Self {
+            label: label.to_string(),
+            label_align: Alignment::Left,
+            split_percentage: 40,
+            options,
+            chosen: 0,
+            selected: false,
+            area: Rect::default(),
+            option_areas: Vec::new(),
+        }
+    }
+
+    pub fn set_chosen(&mut self, chosen: usize) -> &mut Self {
+        self.chosen = chosen.min(self.options.len().saturating_sub(1));
+        self
+    }
+
+    pub fn set_selected(&mut self, selected: bool) -> &mut Self {
+        if selected != self.selected {
+            self.on_focus(selected);
+        }
+        self
+    }
+
+    pub fn set_alignment(&mut self, alignment: Alignment) -> &mut Self {
+        self.label_align = alignment;
+        self
+    }
+
+    pub fn set_split(&mut self, percentage: u16) -> &mut Self {
+        self.split_percentage = percentage;
+        self
+    }
+
+    pub fn get_chosen(&self) -> Option<&String> {
+        self.options.get(self.chosen)
+    }
+
+    fn choose_previous(&mut self) {
+        self.set_chosen(self.chosen.saturating_sub(1));
+    }
+
+    fn choose_next(&mut self) {
+        self.set_chosen(self.chosen + 1);
+    }
+}
+
+impl Widget for RadioGroupWidget {
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
+
+        let split =
+            split_rect(self.split_percentage, Direction::Horizontal, area);
+
+        let label_style = if self.selected {
+            ctx.theme.focus.to_style()
+        } else {
+            Style::default()
+        };
+        let label =
+            Paragraph::new(Span::styled(self.label.clone(), label_style))
+                .alignment(self.label_align);
+        frame.render_widget(label, split[0]);
+
+        let mut spans = Vec::new();
+        let mut column = split[1].x;
+        self.option_areas.clear();
+        for (idx, option) in self.options.iter().enumerate() {
+            let (mark, color) = if idx == self.chosen {
+                ("(•)", ctx.theme.palette.accent)
+            } else {
+                ("( )", ctx.theme.palette.muted)
+            };
+            let text = format!("{} {}", mark, option);
+            let width = text.chars().count() as u16;
+
+            self.option_areas.push((column, column + width));
+            column += width + OPTION_GAP;
+
+            spans.push(Span::styled(text, Style::default().fg(color)));
+            spans.push(Span::raw(" ".repeat(OPTION_GAP as usize)));
+        }
+
+        frame.render_widget(Paragraph::new(Spans::from(spans)), split[1]);
+    }
+
+    fn on_key(&mut self, _ctx: &Context, _key: KeyEvent) {}
+
+    fn on_action(&mut self, _ctx: &Context, action: Action) {
+        if !self.selected {
+            return;
+        }
+
+        match action {
+            Action::SelectLeft => self.choose_previous(),
+            Action::SelectRight => self.choose_next(),
+            _ => {},
+        }
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![Action::SelectLeft, Action::SelectRight]
+    }
+
+    fn on_mouse(&mut self, _ctx: &Context, event: MouseEvent) {
+        if event.kind != MouseEventKind::Down(MouseButton::Left)
+            || !area_contains(self.area, event.column, event.row)
+        {
+            return;
+        }
+
+        let clicked = self
+            .option_areas
+            .iter()
+            .position(|(start, end)| (*start..*end).contains(&event.column));
+        if let Some(idx) = clicked {
+            self.set_chosen(idx);
+        }
+    }
+
+    fn on_tick(&mut self, _ctx: &Context) {}
+
+    fn on_focus(&mut self, arrive: bool) {
+        self.selected = arrive;
+    }
+
+    fn has_focus(&mut self) -> bool {
+        self.selected
+    }
+
+    fn is_valid(&mut self) -> bool {
+        true
+    }
+
+    fn get_value(&self) -> Option<String> {
+        self.get_chosen().cloned()
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui::widget::test::{context, lines, render};
+
+    fn options() -> Vec<String> {
+        vec!["Low".to_string(), "High".to_string()]
+    }
+
+    #[test]
+    fn renders_label_and_options() {
+        let (ctx, _receiver) = context();
+        let mut radio = RadioGroupWidget::new("Level", options());
+
+        let buffer = render(&mut radio, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Level       (•) Low  ( ) High "]);
+        assert_eq!(buffer.get(12, 0).fg, ctx.theme.palette.accent);
+        assert_eq!(buffer.get(21, 0).fg, ctx.theme.palette.muted);
+    }
+
+    #[test]
+    fn chooses_with_actions() {
+        let (ctx, _receiver) = context();
+        let mut radio = RadioGroupWidget::new("Level", options())
+            .set_selected(true)
+            .to_owned();
+
+        radio.on_action(&ctx, Action::SelectRight);
+        let buffer = render(&mut radio, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Level       ( ) Low  (•) High "]);
+
+        radio.on_action(&ctx, Action::SelectLeft);
+        let buffer = render(&mut radio, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Level       (•) Low  ( ) High "]);
+    }
+}
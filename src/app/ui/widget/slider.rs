@@ -0,0 +1,251 @@
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Direction, Rect},
+    style::Style,
+    terminal::Frame,
+    text::{Span, Spans},
+    widgets::Paragraph,
+};
+
+use super::Widget;
+use crate::app::{
+    context::Context,
+    helper::{area_contains, split_rect},
+    keymap::Action,
+};
+
+// Slider for picking a number within a range
+#[derive(Debug, Clone)]
+pub struct SliderWidget {
+    pub label: String,
+    pub label_align: Alignment,
+    pub split_percentage: u16,
+    pub value: i64,
+    pub min: i64,
+    pub max: i64,
+    pub step: i64,
+    pub selected: bool,
+    area: Rect,
+    // Area of the track from the last render
+    track_area: Rect,
+}
+
+#[allow(dead_code)]
+impl SliderWidget {
+    pub fn new<T: ToString>(label: T, min: i64, max: i64) -> Self {
+        Self {
+            label: label.to_string(),
+            label_align: Alignment::Left,
+            split_percentage: 40,
+            value: min,
+            min,
+            max: max.max(min),
+            step: 1,
+            selected: false,
+            area: Rect::default(),
+            track_area: Rect::default(),
+        }
+    }
+
+    pub fn set_value(&mut self, value: i64) -> &mut Self {
+        self.value = value.max(self.min).min(self.max);
+        self
+    }
+
+    pub fn set_step(&mut self, step: i64) -> &mut Self {
+        self.step = step.max(1);
+        self
+    }
+
+    pub fn set_selected(&mut self, selected: bool) -> &mut Self {
+        if selected != self.selected {
+            self.on_focus(selected);
+        }
+        self
+    }
+
+    pub fn set_alignment(&mut self, alignment: Alignment) -> &mut Self {
+        self.label_align = alignment;
+        self
+    }
+
+    pub fn set_split(&mut self, percentage: u16) -> &mut Self {
+        self.split_percentage = percentage;
+        self
+    }
+
+    fn decrease(&mut self) {
+        self.set_value(self.value.saturating_sub(self.step));
+    }
+
+    fn increase(&mut self) {
+        self.set_value(self.value.saturating_add(self.step));
+    }
+}
+
+impl Widget for SliderWidget {
+    fn render<B: Backend>(
+        &mut self,
+        area: Rect,
+        frame: &mut Frame<B>,
+        ctx: &Context,
+    ) {
+        self.area = area;
+
+        let split =
+            split_rect(self.split_percentage, Direction::Horizontal, area);
+
+        let label_style = if self.selected {
+            ctx.theme.focus.to_style()
+        } else {
+            Style::default()
+        };
+        let label =
+            Paragraph::new(Span::styled(self.label.clone(), label_style))
+                .alignment(self.label_align);
+        frame.render_widget(label, split[0]);
+
+        // Leave space for the largest value after the track
+        let value_width =
+            self.min.to_string().len().max(self.max.to_string().len());
+        let track_width =
+            (split[1].width as usize).saturating_sub(value_width + 1);
+        self.track_area =
+            Rect::new(split[1].x, split[1].y, track_width as u16, 1);
+
+        let range = (self.max - self.min).max(1);
+        let filled = if track_width == 0 {
+            0
+        } else {
+            ((self.value - self.min) * (track_width as i64 - 1) / range)
+                as usize
+        };
+
+        let color = if self.selected {
+            ctx.theme.palette.accent
+        } else {
+            ctx.theme.palette.muted
+        };
+        let text = Spans::from(vec![
+            Span::styled("━".repeat(filled), Style::default().fg(color)),
+            Span::styled(
+                if track_width == 0 { "" } else { "●" },
+                Style::default().fg(color),
+            ),
+            Span::styled(
+                "─".repeat(track_width.saturating_sub(filled + 1)),
+                Style::default().fg(ctx.theme.palette.muted),
+            ),
+            Span::raw(format!(" {:>1$}", self.value, value_width)),
+        ]);
+        frame.render_widget(Paragraph::new(text), split[1]);
+    }
+
+    fn on_key(&mut self, _ctx: &Context, _key: KeyEvent) {}
+
+    fn on_action(&mut self, _ctx: &Context, action: Action) {
+        if !self.selected {
+            return;
+        }
+
+        match action {
+            Action::SelectLeft => self.decrease(),
+            Action::SelectRight => self.increase(),
+            Action::First => {
+                self.set_value(self.min);
+            },
+            Action::Last => {
+                self.set_value(self.max);
+            },
+            _ => {},
+        }
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action::SelectLeft,
+            Action::SelectRight,
+            Action::First,
+            Action::Last,
+        ]
+    }
+
+    fn on_mouse(&mut self, _ctx: &Context, event: MouseEvent) {
+        if !area_contains(self.area, event.column, event.row) {
+            return;
+        }
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left)
+                if area_contains(self.track_area, event.column, event.row) =>
+            {
+                let offset = (event.column - self.track_area.x) as i64;
+                let width = (self.track_area.width as i64 - 1).max(1);
+                let value = self.min + offset * (self.max - self.min) / width;
+
+                // Snap to the nearest step
+                let steps = (value - self.min + self.step / 2) / self.step;
+                self.set_value(self.min + steps * self.step);
+            },
+            MouseEventKind::ScrollUp => self.increase(),
+            MouseEventKind::ScrollDown => self.decrease(),
+            _ => {},
+        }
+    }
+
+    fn on_tick(&mut self, _ctx: &Context) {}
+
+    fn on_focus(&mut self, arrive: bool) {
+        self.selected = arrive;
+    }
+
+    fn has_focus(&mut self) -> bool {
+        self.selected
+    }
+
+    fn is_valid(&mut self) -> bool {
+        true
+    }
+
+    fn get_value(&self) -> Option<String> {
+        Some(self.value.to_string())
+    }
+
+    fn get_area(&self) -> Rect {
+        self.area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ui::widget::test::{context, lines, render};
+
+    #[test]
+    fn renders_track_and_value() {
+        let (ctx, _receiver) = context();
+        let mut slider =
+            SliderWidget::new("Volume", 0, 10).set_value(5).to_owned();
+
+        let buffer = render(&mut slider, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Volume      ━━━━━━━●───────  5"]);
+    }
+
+    #[test]
+    fn jumps_to_ends_with_actions() {
+        let (ctx, _receiver) = context();
+        let mut slider = SliderWidget::new("Volume", 0, 10)
+            .set_value(5)
+            .set_selected(true)
+            .to_owned();
+
+        slider.on_action(&ctx, Action::First);
+        let buffer = render(&mut slider, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Volume      ●──────────────  0"]);
+
+        slider.on_action(&ctx, Action::Last);
+        let buffer = render(&mut slider, &ctx, 30, 1);
+        assert_eq!(lines(&buffer), vec!["Volume      ━━━━━━━━━━━━━━● 10"]);
+    }
+}
@@ -1,76 +1,137 @@
-use matrix_sdk::{Client as MatrixClient, ClientConfig};
+use std::fmt;
+
+use matrix_sdk::{Client as MatrixClient, ClientConfig, Session};
 use serde::Deserialize;
+use tracing::{debug, warn};
 use url::Url;
 
-use super::{context::ClientSettings, CLIENT_ID};
-use crate::{fs::DATA_DIRECTORY, handle_login_section};
+use super::{
+    error::ClientError,
+    session::{save_session, StoredSession},
+    CLIENT_ID,
+};
+use crate::fs::DATA_DIRECTORY;
 
-#[derive(Debug, Clone)]
+#[derive(Clone, Default)]
 pub struct AuthCreds {
     pub username:   String,
     pub homeserver: String,
     pub password:   String,
 }
 
+impl AuthCreds {
+    // Fills in the login form if the stored session is no
+    // longer valid
+    pub fn from_session(stored: &StoredSession) -> Self {
+        let user_id = &stored.session.user_id;
+
+        Self {
+            username:   user_id.localpart().to_string(),
+            homeserver: user_id.server_name().to_string(),
+            password:   String::new(),
+        }
+    }
+}
+
+// Keeps the password out of logs
+impl fmt::Debug for AuthCreds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthCreds")
+            .field("username", &self.username)
+            .field("homeserver", &self.homeserver)
+            .field("password", &"[REDACTED]")
+            .finish()
+    }
+}
+
 pub async fn get_home_server(
-    settings: &ClientSettings,
     credentials: &AuthCreds,
-) -> Result<Url, String> {
+) -> Result<Url, ClientError> {
     let url = format!(
         "https://{}/.well-known/matrix/client",
         credentials.homeserver
     );
 
-    let result = handle_login_section!(
-        settings,
-        reqwest::get(url).await,
-        "Unable to connect to home server."
-    );
+    let result = reqwest::get(url).await.map_err(|why| {
+        ClientError::network("Unable to connect to home server.", why)
+    })?;
 
-    let text = handle_login_section!(
-        settings,
-        result.text().await,
-        "Unable to get home server response."
-    );
+    let text = result.text().await.map_err(|why| {
+        ClientError::network("Unable to get home server response.", why)
+    })?;
 
-    let home_server = handle_login_section!(
-        settings,
-        serde_json::from_str::<HomeServerResponse>(&text),
-        "Unable to parse home server response."
-    );
+    let home_server = serde_json::from_str::<HomeServerResponse>(&text)
+        .map_err(|why| {
+            ClientError::invalid_response(
+                "Unable to parse home server response.",
+                why,
+            )
+        })?;
 
-    let url = handle_login_section!(
-        settings,
-        Url::parse(&home_server.homeserver.url),
-        "Home server returned malformed URL."
-    );
+    let url = Url::parse(&home_server.homeserver.url).map_err(|why| {
+        ClientError::invalid_response(
+            "Home server returned malformed URL.",
+            why,
+        )
+    })?;
+    debug!("Using home server {}", url);
 
     Ok(url)
 }
 
-pub async fn login(
-    settings: &ClientSettings,
-    credentials: &AuthCreds,
-    home_server: Url,
-) -> Result<MatrixClient, String> {
+fn create_client(home_server: Url) -> Result<MatrixClient, ClientError> {
     let store_path = DATA_DIRECTORY.as_ref().unwrap();
     let config = ClientConfig::default().store_path(store_path);
 
-    let client = MatrixClient::new_with_config(home_server, config).unwrap();
-    let login = client
+    MatrixClient::new_with_config(home_server, config).map_err(|why| {
+        ClientError::from_matrix("Unable to create client.", why)
+    })
+}
+
+pub async fn login(
+    credentials: &AuthCreds,
+    home_server: Url,
+) -> Result<MatrixClient, ClientError> {
+    let client = create_client(home_server.clone())?;
+    let response = client
         .login(
             &credentials.username.to_lowercase(),
             &credentials.password,
             None,
             Some(&CLIENT_ID),
         )
-        .await;
+        .await
+        .map_err(|why| {
+            ClientError::from_matrix(
+                "Unable to login with provided credentials.",
+                why,
+            )
+        })?;
 
-    handle_login_section!(
-        settings,
-        login,
-        "Unable to login with provided credentials."
-    );
+    // Failing to save only affects the headless commands
+    let session = StoredSession {
+        homeserver: home_server,
+        session:    Session {
+            access_token: response.access_token,
+            user_id:      response.user_id,
+            device_id:    response.device_id,
+        },
+    };
+    if let Err(why) = save_session(&session) {
+        warn!("Unable to save session: {}", why);
+    }
+
+    Ok(client)
+}
+
+// Logs in with a session saved by a previous login
+pub async fn restore_login(
+    stored: StoredSession,
+) -> Result<MatrixClient, ClientError> {
+    let client = create_client(stored.homeserver)?;
+    client.restore_login(stored.session).await.map_err(|why| {
+        ClientError::from_matrix("Unable to restore session.", why)
+    })?;
 
     Ok(client)
 }
@@ -1,35 +1,29 @@
-use std::sync::mpsc::{Receiver, SendError, Sender};
+use std::sync::mpsc::Sender;
 
-use super::ClientNotification;
-use crate::app::context::Notification;
+use tracing::warn;
 
-#[derive(Debug, Default, Clone)]
-pub struct ClientSettings {
-    pub verbose: bool,
-}
+use crate::app::context::Notification;
 
 pub struct Context {
-    sender:       Sender<Notification>,
-    receiver:     Receiver<ClientNotification>,
-    pub settings: ClientSettings,
+    sender: Sender<Notification>,
 }
 
 impl Context {
-    pub fn new(
-        sender: Sender<Notification>,
-        receiver: Receiver<ClientNotification>,
-    ) -> Self {
+    pub fn new(sender: Sender<Notification>) -> Self {
         Self {
             sender,
-            receiver,
-            settings: ClientSettings::default(),
         }
     }
 
-    pub fn send_notification(
-        &self,
-        notification: Notification,
-    ) -> Result<(), SendError<Notification>> {
-        self.sender.send(notification)
+    // Used where the context can't be shared, such as
+    // inside the sync loop
+    pub fn clone_sender(&self) -> Sender<Notification> {
+        self.sender.clone()
+    }
+
+    pub fn send_notification(&self, notification: Notification) {
+        if let Err(why) = self.sender.send(notification) {
+            warn!("Unable to send notification: {}", why);
+        }
     }
 }
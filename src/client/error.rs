@@ -0,0 +1,287 @@
+use std::{error::Error as StdError, fmt, time::Duration};
+
+use matrix_sdk::{
+    ruma::api::{
+        client::error::ErrorKind as ApiErrorKind,
+        error::{FromHttpResponseError, ServerError},
+    },
+    Error as MatrixError, HttpError,
+};
+
+type Source = Box<dyn StdError + Send + Sync>;
+
+#[derive(Debug)]
+pub enum ClientErrorKind {
+    // The request didn't reach the server or no response
+    // was received
+    Network,
+    // The server responded with something that couldn't be
+    // understood
+    InvalidResponse,
+    // The server rejected the request with an error code
+    Matrix(MatrixErrorKind),
+    Other,
+}
+
+// Error codes which have their own message, anything else
+// uses the message sent by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatrixErrorKind {
+    Forbidden,
+    LimitExceeded(Option<Duration>),
+    UserDeactivated,
+    UnknownToken,
+    ResourceLimitExceeded(String),
+    Other(String),
+}
+
+#[derive(Debug)]
+pub struct ClientError {
+    // What was being done, shown above the reason
+    pub context: &'static str,
+    pub kind:    ClientErrorKind,
+    source:      Source,
+}
+
+impl ClientError {
+    pub fn new<E>(
+        context: &'static str,
+        kind: ClientErrorKind,
+        source: E,
+    ) -> Self
+    where
+        E: Into<Source>, {
+        Self {
+            context,
+            kind,
+            source: source.into(),
+        }
+    }
+
+    pub fn network<E: Into<Source>>(context: &'static str, source: E) -> Self {
+        Self::new(context, ClientErrorKind::Network, source)
+    }
+
+    pub fn invalid_response<E: Into<Source>>(
+        context: &'static str,
+        source: E,
+    ) -> Self {
+        Self::new(context, ClientErrorKind::InvalidResponse, source)
+    }
+
+    // Finds the error code sent by the server if there is
+    // one
+    pub fn from_matrix(context: &'static str, error: MatrixError) -> Self {
+        let kind = match &error {
+            MatrixError::Http(HttpError::Reqwest(_)) => {
+                ClientErrorKind::Network
+            },
+            MatrixError::Http(HttpError::ClientApi(
+                FromHttpResponseError::Http(ServerError::Known(api_error)),
+            )) => ClientErrorKind::Matrix(MatrixErrorKind::from_api(
+                &api_error.kind,
+                &api_error.message,
+            )),
+            MatrixError::Http(HttpError::ClientApi(_)) => {
+                ClientErrorKind::InvalidResponse
+            },
+            _ => ClientErrorKind::Other,
+        };
+
+        Self::new(context, kind, error)
+    }
+
+    // Whether trying again later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match &self.kind {
+            ClientErrorKind::Network => true,
+            ClientErrorKind::Matrix(kind) => matches!(
+                kind,
+                MatrixErrorKind::LimitExceeded(_)
+                    | MatrixErrorKind::ResourceLimitExceeded(_)
+            ),
+            ClientErrorKind::InvalidResponse | ClientErrorKind::Other => false,
+        }
+    }
+
+    // Why the request failed in terms the user can act on
+    pub fn reason(&self) -> String {
+        match &self.kind {
+            ClientErrorKind::Network => {
+                "The server couldn't be reached, check your connection and \
+                 try again."
+                    .to_string()
+            },
+            ClientErrorKind::InvalidResponse => {
+                "The server sent a response which couldn't be understood."
+                    .to_string()
+            },
+            ClientErrorKind::Matrix(kind) => kind.reason(),
+            ClientErrorKind::Other => self.source.to_string(),
+        }
+    }
+
+    // The underlying error and its causes, shown when
+    // verbose errors are enabled
+    pub fn details(&self) -> String {
+        let mut details = self.source.to_string();
+
+        let mut source = self.source.source();
+        while let Some(cause) = source {
+            details = format!("{}\nCaused by: {}", details, cause);
+            source = cause.source();
+        }
+
+        details
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n{}", self.context, self.reason())
+    }
+}
+
+impl StdError for ClientError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl MatrixErrorKind {
+    fn from_api(kind: &ApiErrorKind, message: &str) -> Self {
+        match kind {
+            ApiErrorKind::Forbidden => Self::Forbidden,
+            ApiErrorKind::LimitExceeded {
+                retry_after_ms,
+            } => Self::LimitExceeded(*retry_after_ms),
+            ApiErrorKind::UserDeactivated => Self::UserDeactivated,
+            ApiErrorKind::UnknownToken {
+                ..
+            } => Self::UnknownToken,
+            ApiErrorKind::ResourceLimitExceeded {
+                admin_contact,
+            } => Self::ResourceLimitExceeded(admin_contact.clone()),
+            _ => Self::Other(message.to_string()),
+        }
+    }
+
+    fn reason(&self) -> String {
+        match self {
+            Self::Forbidden => {
+                "The server refused the request, check your username and \
+                 password."
+                    .to_string()
+            },
+            Self::LimitExceeded(Some(retry_after)) => {
+                format!(
+                    "Too many requests, try again in {} seconds.",
+                    retry_after.as_secs().max(1)
+                )
+            },
+            Self::LimitExceeded(None) => {
+                "Too many requests, try again later.".to_string()
+            },
+            Self::UserDeactivated => {
+                "This account has been deactivated.".to_string()
+            },
+            Self::UnknownToken => {
+                "Your session has expired, please log in again.".to_string()
+            },
+            Self::ResourceLimitExceeded(admin_contact) => {
+                format!(
+                    "The server has reached a resource limit, contact {} for \
+                     help.",
+                    admin_contact
+                )
+            },
+            Self::Other(message) => message.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_map_to_kinds() {
+        let retry_after = Some(Duration::from_secs(3));
+        let admin = "admin@example.org".to_string();
+        let table = vec![
+            (ApiErrorKind::Forbidden, MatrixErrorKind::Forbidden, false),
+            (
+                ApiErrorKind::LimitExceeded {
+                    retry_after_ms: retry_after,
+                },
+                MatrixErrorKind::LimitExceeded(retry_after),
+                true,
+            ),
+            (
+                ApiErrorKind::LimitExceeded {
+                    retry_after_ms: None,
+                },
+                MatrixErrorKind::LimitExceeded(None),
+                true,
+            ),
+            (
+                ApiErrorKind::UserDeactivated,
+                MatrixErrorKind::UserDeactivated,
+                false,
+            ),
+            (
+                ApiErrorKind::UnknownToken {
+                    soft_logout: true,
+                },
+                MatrixErrorKind::UnknownToken,
+                false,
+            ),
+            (
+                ApiErrorKind::ResourceLimitExceeded {
+                    admin_contact: admin.clone(),
+                },
+                MatrixErrorKind::ResourceLimitExceeded(admin),
+                true,
+            ),
+            // Anything else keeps the server's message
+            (
+                ApiErrorKind::NotFound,
+                MatrixErrorKind::Other("Server message".to_string()),
+                false,
+            ),
+            (
+                ApiErrorKind::Unknown,
+                MatrixErrorKind::Other("Server message".to_string()),
+                false,
+            ),
+        ];
+
+        for (api_kind, expected, retryable) in table {
+            let kind = MatrixErrorKind::from_api(&api_kind, "Server message");
+            assert_eq!(kind, expected, "{:?}", api_kind);
+
+            let error = ClientError::new(
+                "Testing",
+                ClientErrorKind::Matrix(kind),
+                "Server message",
+            );
+            assert_eq!(error.is_retryable(), retryable, "{:?}", api_kind);
+        }
+    }
+
+    #[test]
+    fn only_network_errors_are_retryable_otherwise() {
+        let retryable =
+            |kind| ClientError::new("Testing", kind, "").is_retryable();
+
+        assert!(retryable(ClientErrorKind::Network));
+        assert!(!retryable(ClientErrorKind::InvalidResponse));
+        assert!(!retryable(ClientErrorKind::Other));
+    }
+
+    #[test]
+    fn other_errors_show_server_message() {
+        let kind = MatrixErrorKind::Other("Room not found.".to_string());
+        assert_eq!(kind.reason(), "Room not found.");
+    }
+}
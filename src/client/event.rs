@@ -1,5 +1,169 @@
-use matrix_sdk::EventHandler;
+use std::convert::TryFrom;
+
+use matrix_sdk::{
+    deserialized_responses::SyncResponse,
+    ruma::{serde::Raw, EventId},
+    Client as MatrixClient, EventHandler,
+};
+use serde_json::Value;
+
+use super::hooks::HookEvent;
+use crate::config::HookTrigger;
 
 pub struct EventCallback;
 
 impl EventHandler for EventCallback {}
+
+// New messages and invites from a sync, the user's own
+// messages are skipped
+pub async fn hook_events(
+    client: &MatrixClient,
+    response: &SyncResponse,
+) -> Vec<HookEvent> {
+    let user_id = match client.user_id().await {
+        Some(user_id) => user_id.to_string(),
+        None => return Vec::new(),
+    };
+    let mut events = Vec::new();
+
+    for (room_id, room) in &response.rooms.join {
+        let joined = match client.get_joined_room(room_id) {
+            Some(joined) => joined,
+            None => continue,
+        };
+        let room_name = joined
+            .display_name()
+            .await
+            .unwrap_or_else(|_| room_id.to_string());
+        let room_alias =
+            joined.canonical_alias().map(|alias| alias.to_string());
+
+        for event in &room.timeline.events {
+            let event = match parse_event(&event.event) {
+                Some(event) => event,
+                None => continue,
+            };
+            let sender = event["sender"].as_str().unwrap_or_default();
+            if event["type"] != "m.room.message" || sender == user_id {
+                continue;
+            }
+
+            let body = event["content"]["body"].as_str().unwrap_or_default();
+            let mut triggers = vec![HookTrigger::Message];
+            if is_mention(body, &user_id) {
+                triggers.push(HookTrigger::Mention);
+            }
+            if joined.is_direct() {
+                triggers.push(HookTrigger::Direct);
+            }
+
+            events.push(HookEvent {
+                triggers,
+                room_id: room_id.to_string(),
+                room_name: room_name.clone(),
+                room_alias: room_alias.clone(),
+                sender: sender.to_string(),
+                body: body.to_string(),
+            });
+        }
+    }
+
+    for (room_id, room) in &response.rooms.invite {
+        // Invites are the user's member event, sent by
+        // whoever invited them
+        let sender = room
+            .invite_state
+            .events
+            .iter()
+            .filter_map(parse_event)
+            .find(|event| {
+                event["type"] == "m.room.member"
+                    && event["state_key"] == user_id.as_str()
+            })
+            .and_then(|event| event["sender"].as_str().map(ToString::to_string))
+            .unwrap_or_default();
+        let room_name = match client.get_invited_room(room_id) {
+            Some(invited) => invited
+                .display_name()
+                .await
+                .unwrap_or_else(|_| room_id.to_string()),
+            None => room_id.to_string(),
+        };
+
+        events.push(HookEvent {
+            triggers: vec![HookTrigger::Invite],
+            room_id: room_id.to_string(),
+            room_name,
+            room_alias: None,
+            sender,
+            body: String::new(),
+        });
+    }
+
+    events
+}
+
+// The newest event in each joined room that got any, used
+// for read receipts
+pub fn latest_event_ids(response: &SyncResponse) -> Vec<(String, EventId)> {
+    response
+        .rooms
+        .join
+        .iter()
+        .filter_map(|(room_id, room)| {
+            let event_id =
+                room.timeline.events.iter().rev().find_map(|event| {
+                    let event = parse_event(&event.event)?;
+                    EventId::try_from(event["event_id"].as_str()?).ok()
+                })?;
+
+            Some((room_id.to_string(), event_id))
+        })
+        .collect()
+}
+
+fn parse_event<T>(event: &Raw<T>) -> Option<Value> {
+    serde_json::from_str(event.json().get()).ok()
+}
+
+// Either the full user ID or just the local part as a word
+fn is_mention(body: &str, user_id: &str) -> bool {
+    let body = body.to_lowercase();
+    let user_id = user_id.to_lowercase();
+    if body.contains(&user_id) {
+        return true;
+    }
+
+    let localpart = user_id
+        .trim_start_matches('@')
+        .split(':')
+        .next()
+        .unwrap_or_default();
+    body.split_whitespace().any(|word| {
+        word.trim_matches(|c: char| !c.is_alphanumeric()) == localpart
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_mention;
+
+    const USER_ID: &str = "@alice:example.org";
+
+    #[test]
+    fn full_user_id_is_a_mention() {
+        assert!(is_mention("ping @Alice:example.org please", USER_ID));
+    }
+
+    #[test]
+    fn localpart_has_to_be_a_whole_word() {
+        assert!(is_mention("alice", USER_ID));
+        assert!(is_mention("Hey ALICE, look at this", USER_ID));
+        assert!(is_mention("thanks (alice)!", USER_ID));
+
+        assert!(!is_mention("malice", USER_ID));
+        assert!(!is_mention("alice_bot said hi", USER_ID));
+        assert!(!is_mention("alicesmith", USER_ID));
+        assert!(!is_mention("nobody here", USER_ID));
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    fs::File,
+    io::{stdin, Read},
+    path::{Path, PathBuf},
+};
+
+use matrix_sdk::{
+    room::Joined,
+    ruma::{
+        events::{room::message::MessageEventContent, AnyMessageEventContent},
+        serde::Raw,
+        RoomId,
+    },
+    Client as MatrixClient, LoopCtrl, SyncSettings,
+};
+use mime::Mime;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::{
+    auth::restore_login,
+    error::{ClientError, ClientErrorKind},
+    session::{load_session, StoredSession},
+};
+
+// Commands which are run without starting the TUI, these
+// print their output to stdout so they can be scripted
+#[derive(Debug, Clone)]
+pub enum Command {
+    // Reads the message from stdin if it's not given
+    Send {
+        room:    String,
+        message: Option<String>,
+    },
+    Rooms {
+        json: bool,
+    },
+    // Prints each new message as a line of JSON until
+    // interrupted
+    Tail {
+        room: String,
+    },
+    Upload {
+        room: String,
+        path: PathBuf,
+    },
+    Whoami {
+        json: bool,
+    },
+}
+
+// Also printed for rooms listed by a running instance
+#[derive(Serialize)]
+pub struct RoomSummary {
+    pub id:    String,
+    pub name:  String,
+    pub alias: Option<String>,
+}
+
+pub async fn run(command: Command) -> Result<(), ClientError> {
+    info!("Running headless command {:?}", command);
+
+    // The store isn't opened for commands which only need
+    // the stored session
+    match command {
+        Command::Send {
+            room,
+            message,
+        } => send(&connect().await?, &room, message).await,
+        Command::Rooms {
+            json,
+        } => rooms(&connect().await?, json).await,
+        Command::Tail {
+            room,
+        } => tail(&connect().await?, &room).await,
+        Command::Upload {
+            room,
+            path,
+        } => upload(&connect().await?, &room, &path).await,
+        Command::Whoami {
+            json,
+        } => whoami(json),
+    }
+}
+
+fn other_error<E>(context: &'static str, source: E) -> ClientError
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>, {
+    ClientError::new(context, ClientErrorKind::Other, source)
+}
+
+// Restores the stored session, the rooms are loaded from
+// the store kept by the app so it only has to sync if the
+// app never has
+async fn connect() -> Result<MatrixClient, ClientError> {
+    let client = restore_login(stored_session()?).await?;
+    if client.sync_token().await.is_none() {
+        client
+            .sync_once(SyncSettings::default())
+            .await
+            .map_err(|why| ClientError::from_matrix("Unable to sync.", why))?;
+    }
+
+    Ok(client)
+}
+
+fn stored_session() -> Result<StoredSession, ClientError> {
+    load_session()
+        .map_err(|why| other_error("Unable to load session.", why))?
+        .ok_or_else(|| {
+            other_error(
+                "Not logged in.",
+                "No stored session was found, log in from the app first.",
+            )
+        })
+}
+
+// Rooms can be given by ID, alias or display name
+async fn find_room(
+    client: &MatrixClient,
+    query: &str,
+) -> Result<Joined, ClientError> {
+    let mut named = Vec::new();
+    for room in client.joined_rooms() {
+        let alias = room.canonical_alias().map(|alias| alias.to_string());
+        if room.room_id().as_str() == query || alias.as_deref() == Some(query) {
+            return Ok(room);
+        }
+
+        if room.display_name().await.ok().as_deref() == Some(query) {
+            named.push(room);
+        }
+    }
+
+    match named.len() {
+        1 => Ok(named.remove(0)),
+        0 => Err(other_error(
+            "Unable to find room.",
+            format!("No joined room matches '{}'.", query),
+        )),
+        _ => Err(other_error(
+            "Unable to find room.",
+            format!(
+                "{} rooms are named '{}', use the room ID or alias instead.",
+                named.len(),
+                query
+            ),
+        )),
+    }
+}
+
+// Reads the message from stdin if it wasn't given, this is
+// also used when forwarding to a running instance
+pub fn message_body(message: Option<String>) -> Result<String, ClientError> {
+    let message = match message {
+        Some(message) => message,
+        None => {
+            let mut message = String::new();
+            stdin().read_to_string(&mut message).map_err(|why| {
+                other_error("Unable to read message from stdin.", why)
+            })?;
+
+            message.trim_end_matches('\n').to_string()
+        },
+    };
+    if message.trim().is_empty() {
+        return Err(other_error(
+            "Unable to send message.",
+            "The message is empty.",
+        ));
+    }
+
+    Ok(message)
+}
+
+async fn send(
+    client: &MatrixClient,
+    room: &str,
+    message: Option<String>,
+) -> Result<(), ClientError> {
+    let message = message_body(message)?;
+    let room = find_room(client, room).await?;
+    let content = AnyMessageEventContent::RoomMessage(
+        MessageEventContent::text_plain(message),
+    );
+    let response = room.send(content, None).await.map_err(|why| {
+        ClientError::from_matrix("Unable to send message.", why)
+    })?;
+
+    println!("{}", response.event_id);
+    Ok(())
+}
+
+async fn rooms(client: &MatrixClient, json: bool) -> Result<(), ClientError> {
+    let mut rooms = Vec::new();
+    for room in client.joined_rooms() {
+        rooms.push(RoomSummary {
+            id:    room.room_id().to_string(),
+            name:  room.display_name().await.unwrap_or_default(),
+            alias: room.canonical_alias().map(|alias| alias.to_string()),
+        });
+    }
+
+    print_rooms(rooms, json)
+}
+
+pub fn print_rooms(
+    mut rooms: Vec<RoomSummary>,
+    json: bool,
+) -> Result<(), ClientError> {
+    rooms.sort_by_key(|room| room.name.to_lowercase());
+
+    if json {
+        let text = serde_json::to_string_pretty(&rooms).map_err(|why| {
+            other_error("Unable to serialize room list.", why)
+        })?;
+        println!("{}", text);
+    } else {
+        for room in rooms {
+            println!("{}\t{}", room.id, room.name);
+        }
+    }
+
+    Ok(())
+}
+
+async fn tail(client: &MatrixClient, room: &str) -> Result<(), ClientError> {
+    let room_id = find_room(client, room).await?.room_id().clone();
+
+    // Continue from the initial sync so only new messages
+    // are printed
+    let mut settings = SyncSettings::default();
+    if let Some(token) = client.sync_token().await {
+        settings = settings.token(token);
+    }
+
+    client
+        .sync_with_callback(settings, |response| {
+            let room_id = room_id.clone();
+
+            async move {
+                if let Some(room) = response.rooms.join.get(&room_id) {
+                    room.timeline
+                        .events
+                        .iter()
+                        .filter_map(|event| {
+                            message_line(&room_id, &event.event)
+                        })
+                        .for_each(|line| println!("{}", line));
+                }
+
+                LoopCtrl::Continue
+            }
+        })
+        .await;
+
+    Ok(())
+}
+
+// Sync events don't include the room ID so it's added to
+// each message
+fn message_line<T>(room_id: &RoomId, event: &Raw<T>) -> Option<String> {
+    let mut event = serde_json::from_str::<Value>(event.json().get()).ok()?;
+    if event["type"] != "m.room.message" {
+        return None;
+    }
+
+    event
+        .as_object_mut()?
+        .insert("room_id".to_string(), json!(room_id.as_str()));
+
+    Some(event.to_string())
+}
+
+async fn upload(
+    client: &MatrixClient,
+    room: &str,
+    path: &Path,
+) -> Result<(), ClientError> {
+    let mut file = File::open(path)
+        .map_err(|why| other_error("Unable to open file.", why))?;
+
+    let room = find_room(client, room).await?;
+    let response = room
+        .send_attachment(
+            &attachment_name(path),
+            &guess_mime(path),
+            &mut file,
+            None,
+        )
+        .await
+        .map_err(|why| {
+            ClientError::from_matrix("Unable to upload file.", why)
+        })?;
+
+    println!("{}", response.event_id);
+    Ok(())
+}
+
+pub fn attachment_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Only common types are recognised, anything else is sent
+// as a generic file
+pub fn guess_mime(path: &Path) -> Mime {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => mime::IMAGE_PNG,
+        "jpg" | "jpeg" => mime::IMAGE_JPEG,
+        "gif" => mime::IMAGE_GIF,
+        "bmp" => mime::IMAGE_BMP,
+        "svg" => mime::IMAGE_SVG,
+        "txt" | "log" => mime::TEXT_PLAIN,
+        "json" => mime::APPLICATION_JSON,
+        "pdf" => mime::APPLICATION_PDF,
+        _ => mime::APPLICATION_OCTET_STREAM,
+    }
+}
+
+// Only reads the stored session so it can be run while the
+// app is open
+pub fn whoami(json: bool) -> Result<(), ClientError> {
+    let stored = stored_session()?;
+    let user_id = stored.session.user_id.to_string();
+
+    if json {
+        let value = json!({
+            "user_id": user_id,
+            "device_id": stored.session.device_id.to_string(),
+            "homeserver": stored.homeserver.to_string(),
+        });
+        println!("{}", value);
+    } else {
+        println!("{}", user_id);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,343 @@
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::{
+    collections::VecDeque,
+    io::Write,
+    process::{Child, Command, Stdio},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::config::{HookConfig, HookTrigger};
+
+// How often a running command is checked for exiting
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// Details of an event which hooks are matched against, this
+// is also what's sent to the command on stdin and passed to
+// scripts
+#[derive(Debug, Clone, Serialize)]
+pub struct HookEvent {
+    // Every trigger the event matches other than keywords,
+    // which depend on the hook
+    pub triggers:   Vec<HookTrigger>,
+    pub room_id:    String,
+    pub room_name:  String,
+    pub room_alias: Option<String>,
+    pub sender:     String,
+    pub body:       String,
+}
+
+#[derive(Serialize)]
+struct HookInput<'a> {
+    event:   HookTrigger,
+    #[serde(flatten)]
+    details: &'a HookEvent,
+}
+
+struct Hook {
+    config: HookConfig,
+    // Times the hook was run within the rate window
+    runs:   Mutex<VecDeque<Instant>>,
+}
+
+impl Hook {
+    // Returns the first of the hook's events which matches
+    fn matches(&self, event: &HookEvent) -> Option<HookTrigger> {
+        let rooms = &self.config.rooms;
+        if !rooms.is_empty()
+            && !rooms.iter().any(|room| {
+                *room == event.room_id
+                    || *room == event.room_name
+                    || event.room_alias.as_ref() == Some(room)
+            })
+        {
+            return None;
+        }
+
+        let body = event.body.to_lowercase();
+        self.config
+            .events
+            .iter()
+            .copied()
+            .find(|trigger| match trigger {
+                HookTrigger::Keyword => self
+                    .config
+                    .keywords
+                    .iter()
+                    .any(|keyword| body.contains(&keyword.to_lowercase())),
+                _ => event.triggers.contains(trigger),
+            })
+    }
+
+    // Records a run unless the limit has been reached
+    fn try_run(&self) -> bool {
+        let mut runs = match self.runs.lock() {
+            Ok(runs) => runs,
+            Err(_) => return false,
+        };
+
+        let now = Instant::now();
+        while let Some(run) = runs.front() {
+            if now.duration_since(*run) < RATE_WINDOW {
+                break;
+            }
+            runs.pop_front();
+        }
+
+        if runs.len() >= self.config.max_per_minute {
+            return false;
+        }
+        runs.push_back(now);
+
+        true
+    }
+}
+
+pub struct Hooks {
+    hooks: Vec<Hook>,
+}
+
+impl Hooks {
+    pub fn new(configs: Vec<HookConfig>) -> Self {
+        let hooks = configs
+            .into_iter()
+            .map(|config| Hook {
+                config,
+                runs: Mutex::new(VecDeque::new()),
+            })
+            .collect();
+
+        Self {
+            hooks,
+        }
+    }
+
+    // Commands are run on their own threads so slow hooks
+    // don't hold up syncing
+    pub fn dispatch(&self, event: &HookEvent) {
+        for hook in &self.hooks {
+            let trigger = match hook.matches(event) {
+                Some(trigger) => trigger,
+                None => continue,
+            };
+
+            if !hook.try_run() {
+                debug!("Hook '{}' is rate limited", hook.config.command);
+                continue;
+            }
+
+            let command = hook.config.command.clone();
+            let timeout = Duration::from_secs(hook.config.timeout);
+            let event = event.clone();
+            thread::spawn(move || {
+                run_command(&command, trigger, &event, timeout)
+            });
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    // Started as its own process group so anything the
+    // command runs can be killed along with it
+    shell.process_group(0);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    // The group ID is the same as the shell's process ID,
+    // see `shell`
+    let group = -(child.id() as i32);
+    if unsafe { libc::kill(group, libc::SIGKILL) } != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+}
+
+fn run_command(
+    command: &str,
+    trigger: HookTrigger,
+    event: &HookEvent,
+    timeout: Duration,
+) {
+    let input = HookInput {
+        event:   trigger,
+        details: event,
+    };
+    let json = match serde_json::to_string(&input) {
+        Ok(json) => json,
+        Err(why) => {
+            warn!("Unable to serialize hook event: {}", why);
+            return;
+        },
+    };
+
+    info!("Running hook '{}' for {}", command, trigger.name());
+    let child = shell(command)
+        .env("KONOHA_EVENT", trigger.name())
+        .env("KONOHA_ROOM_ID", &event.room_id)
+        .env("KONOHA_ROOM_NAME", &event.room_name)
+        .env(
+            "KONOHA_ROOM_ALIAS",
+            event.room_alias.as_deref().unwrap_or(""),
+        )
+        .env("KONOHA_SENDER", &event.sender)
+        .env("KONOHA_BODY", &event.body)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(why) => {
+            warn!("Unable to run hook '{}': {}", command, why);
+            return;
+        },
+    };
+
+    // Written separately so a command which doesn't read
+    // stdin can't block the timeout, errors are expected if
+    // it exits early
+    if let Some(mut stdin) = child.stdin.take() {
+        thread::spawn(move || {
+            let _ = stdin.write_all(json.as_bytes());
+        });
+    }
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return,
+            Ok(Some(status)) => {
+                warn!("Hook '{}' exited with {}", command, status);
+                return;
+            },
+            Ok(None) if started.elapsed() >= timeout => {
+                warn!("Hook '{}' timed out, killing it", command);
+                kill(&mut child);
+                let _ = child.wait();
+                return;
+            },
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(why) => {
+                warn!("Unable to wait for hook '{}': {}", command, why);
+                return;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(max_per_minute: usize) -> Hook {
+        Hook {
+            config: HookConfig {
+                command: "true".to_string(),
+                events: vec![HookTrigger::Message],
+                keywords: Vec::new(),
+                rooms: Vec::new(),
+                timeout: 10,
+                max_per_minute,
+            },
+            runs:   Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn event() -> HookEvent {
+        HookEvent {
+            triggers:   vec![HookTrigger::Message],
+            room_id:    "!room:example.org".to_string(),
+            room_name:  "Room".to_string(),
+            room_alias: None,
+            sender:     "@alice:example.org".to_string(),
+            body:       "Hello".to_string(),
+        }
+    }
+
+    #[test]
+    fn runs_are_limited_per_minute() {
+        let hook = hook(2);
+        assert!(hook.try_run());
+        assert!(hook.try_run());
+        assert!(!hook.try_run());
+    }
+
+    #[test]
+    fn runs_outside_the_window_are_forgotten() {
+        let hook = hook(1);
+        let now = Instant::now();
+        let expired = now.checked_sub(RATE_WINDOW + Duration::from_secs(1));
+        let recent = now.checked_sub(RATE_WINDOW - Duration::from_secs(1));
+        let (expired, recent) = match (expired, recent) {
+            (Some(expired), Some(recent)) => (expired, recent),
+            // The clock started too recently to go back a minute
+            _ => return,
+        };
+
+        hook.runs.lock().unwrap().push_back(expired);
+        assert!(hook.try_run());
+        assert_eq!(hook.runs.lock().unwrap().len(), 1);
+
+        let mut runs = hook.runs.lock().unwrap();
+        runs.clear();
+        runs.push_back(recent);
+        drop(runs);
+        assert!(!hook.try_run());
+    }
+
+    // Commands started by the hook have to be killed as well
+    // as the shell, otherwise they keep running in the
+    // background
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn timeout_kills_whole_process_group() {
+        use std::{env, fs, process};
+
+        let pid_file =
+            env::temp_dir().join(format!("konoha-hook-test-{}", process::id()));
+        let command =
+            format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        run_command(
+            &command,
+            HookTrigger::Message,
+            &event(),
+            Duration::from_millis(500),
+        );
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        fs::remove_file(&pid_file).unwrap();
+
+        // Killed processes are zombies until they're reaped
+        let stat = format!("/proc/{}/stat", pid.trim());
+        let alive = || {
+            fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z"))
+        };
+        let started = Instant::now();
+        while alive() && started.elapsed() < Duration::from_secs(2) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(!alive());
+    }
+}
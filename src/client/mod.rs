@@ -1,28 +1,82 @@
-use std::sync::mpsc::{self, Sender};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
 
 use clap::{crate_name, crate_version};
 use lazy_static::lazy_static;
-use matrix_sdk::SyncSettings;
+use matrix_sdk::{Client as MatrixClient, LoopCtrl, SyncSettings};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+use tracing::{info, warn};
 
 use self::{
-    auth::{get_home_server, login, AuthCreds},
+    auth::{get_home_server, login, restore_login, AuthCreds},
     context::Context,
+    event::hook_events,
+    hooks::{HookEvent, Hooks},
+    room::{
+        changed_room_entries, join_room, mark_read, redact, room_entries,
+        rooms_changed, send_message, update_latest_events, upload_file,
+        LatestEvents, SentEvents,
+    },
+    session::StoredSession,
 };
 use crate::{
     app::{
         context::Notification,
-        ui::prelude::{AuthenticateMenu, LoadingMenu},
+        ui::prelude::{AuthenticateMenu, LoadingMenu, Severity, Toast},
     },
+    config::HookConfig,
     handle_login,
 };
 
 pub mod auth;
 mod context;
+pub mod error;
 mod event;
+pub mod headless;
+pub mod hooks;
 pub mod macros;
+mod room;
+pub mod session;
 
 pub enum ClientNotification {
     Test,
+    JoinRoom(String),
+    // Rooms are given by ID
+    SendMessage {
+        room: String,
+        body: String,
+        kind: MessageKind,
+    },
+    MarkRead(String),
+    // Redacts the last message sent to the room
+    Redact(String),
+    Upload {
+        room: String,
+        path: PathBuf,
+    },
+    // Stops syncing and closes the store
+    Shutdown,
+}
+
+// Whether a message starts something new or follows on from
+// an earlier event in the room
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    New,
+    // Replies to the newest event in the room
+    Reply,
+    // Replaces the last message sent to the room
+    Edit,
 }
 
 lazy_static! {
@@ -40,48 +94,248 @@ lazy_static! {
     );
 }
 
+// Kept by the app so that it can wait for the client to
+// stop when quitting
+pub struct ClientHandle {
+    task:    JoinHandle<()>,
+    // Disconnected once the client has been dropped
+    stopped: Receiver<()>,
+}
+
+impl ClientHandle {
+    // Waits for the client to stop after being sent
+    // `ClientNotification::Shutdown`, it's aborted if it
+    // takes longer than the timeout
+    pub fn wait(self, timeout: Duration) {
+        if let Err(RecvTimeoutError::Timeout) =
+            self.stopped.recv_timeout(timeout)
+        {
+            warn!("Client didn't stop in time, aborting");
+            self.task.abort();
+        }
+    }
+}
+
 pub struct Client {
     credentials: AuthCreds,
+    // Used instead of the password when there is one
+    session:     Option<StoredSession>,
+    hooks:       Hooks,
     pub context: Context,
+    receiver:    UnboundedReceiver<ClientNotification>,
 }
 
 impl Client {
     pub fn new(
         credentials: AuthCreds,
+        session: Option<StoredSession>,
+        hooks: Vec<HookConfig>,
         sender: Sender<Notification>,
-    ) -> (Self, Sender<ClientNotification>) {
-        let (app_sender, receiver) = mpsc::channel();
-        let context = Context::new(sender, receiver);
+    ) -> (Self, UnboundedSender<ClientNotification>) {
+        let (app_sender, receiver) = unbounded_channel();
+        let context = Context::new(sender);
 
         (
             Self {
                 credentials,
+                session,
+                hooks: Hooks::new(hooks),
                 context,
+                receiver,
             },
             app_sender,
         )
     }
 
+    pub fn spawn(mut self) -> ClientHandle {
+        let (stopped_sender, stopped) = mpsc::channel();
+        let task = tokio::task::spawn(async move {
+            self.login().await;
+            drop(stopped_sender);
+        });
+
+        ClientHandle {
+            task,
+            stopped,
+        }
+    }
+
     pub async fn login(&mut self) {
-        let settings = &self.context.settings;
+        let client = match self.session.take() {
+            Some(stored) => {
+                handle_login!(self, restore_login(stored), "Restoring session")
+            },
+            None => {
+                let home_server = handle_login!(
+                    self,
+                    get_home_server(&self.credentials),
+                    "Fetching home server"
+                );
 
-        let home_server = handle_login!(
-            self,
-            get_home_server(settings, &self.credentials),
-            "Fetching home server"
-        );
+                handle_login!(
+                    self,
+                    login(&self.credentials, home_server),
+                    "Logging in"
+                )
+            },
+        };
 
-        let client = handle_login!(
-            self,
-            login(&settings, &self.credentials, home_server),
-            "Logging in"
+        let message = format!(
+            "Logged in as @{}:{}.",
+            self.credentials.username, self.credentials.homeserver
         );
+        info!("{}", message);
+        let toast = Toast::new(message, Severity::Success);
+        self.context
+            .send_notification(Notification::ShowToast(toast));
 
-        // TODO: Logging
         let menu = LoadingMenu::new("Syncing data (this may take a while)");
         let notification = Notification::SwitchMenu(Box::new(menu));
-        let _ = self.context.send_notification(notification);
+        self.context.send_notification(notification);
+
+        info!("Starting sync");
+        // Stopping the sync part way through is safe as each
+        // response is saved to the store in one transaction
+        let latest_events = LatestEvents::default();
+        let sent_events = SentEvents::default();
+        let sync = sync(
+            &client,
+            &self.hooks,
+            &latest_events,
+            self.context.clone_sender(),
+        );
+        let notifications = handle_notifications(
+            &client,
+            &self.context,
+            &latest_events,
+            &sent_events,
+            &mut self.receiver,
+        );
+        tokio::select! {
+            _ = sync => {},
+            _ = notifications => {},
+        }
+
+        // Dropping the last handle closes the store, which
+        // flushes it to disk
+        drop(client);
+        info!("Stopped client");
+    }
+}
+
+async fn sync(
+    client: &MatrixClient,
+    hooks: &Hooks,
+    latest_events: &LatestEvents,
+    sender: Sender<Notification>,
+) {
+    // The first response may include a lot of older events
+    // so hooks and scripts only get later ones
+    let initial_sync = AtomicBool::new(true);
+    let sender = Mutex::new(sender);
+    client
+        .sync_with_callback(SyncSettings::default(), |response| {
+            let initial_sync = &initial_sync;
+            let sender = &sender;
+
+            async move {
+                update_latest_events(latest_events, &response);
+
+                let initial = initial_sync.swap(false, Ordering::SeqCst);
+                if !initial {
+                    let events = hook_events(client, &response).await;
+                    for event in &events {
+                        hooks.dispatch(event);
+                    }
+                    send_events(sender, events);
+                }
+
+                if initial {
+                    let rooms = room_entries(client, latest_events).await;
+                    send_notification(sender, Notification::SetRooms(rooms));
+                } else if rooms_changed(&response) {
+                    let (rooms, left) =
+                        changed_room_entries(client, latest_events, &response)
+                            .await;
+                    let notification = Notification::UpdateRooms(rooms, left);
+                    send_notification(sender, notification);
+                }
+
+                LoopCtrl::Continue
+            }
+        })
+        .await;
+}
+
+// Handles requests from the app until it asks the client to
+// stop or is closed
+async fn handle_notifications(
+    client: &MatrixClient,
+    context: &Context,
+    latest_events: &LatestEvents,
+    sent_events: &SentEvents,
+    receiver: &mut UnboundedReceiver<ClientNotification>,
+) {
+    while let Some(notification) = receiver.recv().await {
+        match notification {
+            ClientNotification::Test => {},
+            ClientNotification::JoinRoom(room) => {
+                join_room(client, context, &room).await
+            },
+            ClientNotification::SendMessage {
+                room,
+                body,
+                kind,
+            } => {
+                send_message(
+                    client,
+                    context,
+                    latest_events,
+                    sent_events,
+                    &room,
+                    body,
+                    kind,
+                )
+                .await
+            },
+            ClientNotification::MarkRead(room) => {
+                mark_read(client, context, latest_events, &room).await
+            },
+            ClientNotification::Redact(room) => {
+                redact(client, context, sent_events, &room).await
+            },
+            ClientNotification::Upload {
+                room,
+                path,
+            } => upload_file(client, context, &room, &path).await,
+            ClientNotification::Shutdown => return,
+        }
+    }
+}
 
-        client.sync(SyncSettings::default()).await;
+fn send_notification(
+    sender: &Mutex<Sender<Notification>>,
+    notification: Notification,
+) {
+    if let Ok(sender) = sender.lock() {
+        if let Err(why) = sender.send(notification) {
+            warn!("Unable to send notification: {}", why);
+        }
+    }
+}
+
+// Passes events on to the app for scripts, this isn't async
+// so the lock is never held across an await
+fn send_events(sender: &Mutex<Sender<Notification>>, events: Vec<HookEvent>) {
+    let sender = match sender.lock() {
+        Ok(sender) => sender,
+        Err(_) => return,
+    };
+
+    for event in events {
+        if let Err(why) = sender.send(Notification::RoomEvent(event)) {
+            warn!("Unable to send room event: {}", why);
+            return;
+        }
     }
 }
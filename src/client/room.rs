@@ -0,0 +1,401 @@
+use std::{
+    collections::HashMap, convert::TryFrom, fs::File, path::Path, sync::Mutex,
+};
+
+use matrix_sdk::{
+    deserialized_responses::SyncResponse,
+    room::{Invited, Joined},
+    ruma::{
+        events::{room::message::MessageEventContent, AnyMessageEventContent},
+        EventId, RoomId, RoomIdOrAliasId,
+    },
+    Client as MatrixClient,
+};
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::{
+    context::Context,
+    error::{ClientError, ClientErrorKind},
+    event::latest_event_ids,
+    headless::{attachment_name, guess_mime},
+    MessageKind,
+};
+use crate::app::{
+    context::Notification,
+    room::{RoomEntry, RoomKind},
+    ui::prelude::{Severity, Toast},
+};
+
+// The newest event seen in each room by ID, read receipts
+// and replies are sent for these
+pub type LatestEvents = Mutex<HashMap<String, EventId>>;
+// The last message sent to each room by ID this session,
+// edits and redactions act on these
+pub type SentEvents = Mutex<HashMap<String, EventId>>;
+
+pub fn update_latest_events(latest: &LatestEvents, response: &SyncResponse) {
+    if let Ok(mut latest) = latest.lock() {
+        latest.extend(latest_event_ids(response));
+    }
+}
+
+// Whether a sync joined, left or changed any rooms so the
+// room list and unread counts need updating
+pub fn rooms_changed(response: &SyncResponse) -> bool {
+    !response.rooms.join.is_empty()
+        || !response.rooms.invite.is_empty()
+        || !response.rooms.leave.is_empty()
+}
+
+// Summaries of every joined room and invite for the room
+// switcher
+pub async fn room_entries(
+    client: &MatrixClient,
+    latest: &LatestEvents,
+) -> Vec<RoomEntry> {
+    let mut entries = Vec::new();
+
+    for room in client.joined_rooms() {
+        entries.push(joined_entry(&room, latest).await);
+    }
+    for room in client.invited_rooms() {
+        entries.push(invited_entry(&room).await);
+    }
+
+    entries
+}
+
+// Summaries of only the rooms in the sync along with the
+// IDs of rooms which were left, most syncs only touch a few
+// rooms so the rest aren't looked up again
+pub async fn changed_room_entries(
+    client: &MatrixClient,
+    latest: &LatestEvents,
+    response: &SyncResponse,
+) -> (Vec<RoomEntry>, Vec<String>) {
+    let mut entries = Vec::new();
+
+    for room_id in response.rooms.join.keys() {
+        if let Some(room) = client.get_joined_room(room_id) {
+            entries.push(joined_entry(&room, latest).await);
+        }
+    }
+    for room_id in response.rooms.invite.keys() {
+        if let Some(room) = client.get_invited_room(room_id) {
+            entries.push(invited_entry(&room).await);
+        }
+    }
+
+    let left = response
+        .rooms
+        .leave
+        .keys()
+        .map(ToString::to_string)
+        .collect();
+    (entries, left)
+}
+
+async fn joined_entry(room: &Joined, latest: &LatestEvents) -> RoomEntry {
+    let id = room.room_id().to_string();
+    let kind = if room.is_direct() {
+        RoomKind::Direct
+    } else {
+        RoomKind::Room
+    };
+    // Only direct rooms list their members so they can be
+    // found by who they're with, large rooms would be slow
+    let members = match kind {
+        RoomKind::Direct => room
+            .joined_members()
+            .await
+            .map(|members| {
+                members
+                    .iter()
+                    .map(|member| member.name().to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    RoomEntry {
+        name: room.display_name().await.unwrap_or_else(|_| id.clone()),
+        alias: room.canonical_alias().map(|alias| alias.to_string()),
+        latest_event: event_id(latest, &id),
+        id,
+        kind,
+        members,
+        unread: room.unread_notification_counts().notification_count,
+        last_viewed: None,
+    }
+}
+
+async fn invited_entry(room: &Invited) -> RoomEntry {
+    let id = room.room_id().to_string();
+
+    RoomEntry {
+        name: room.display_name().await.unwrap_or_else(|_| id.clone()),
+        alias: None,
+        id,
+        kind: RoomKind::Invite,
+        members: Vec::new(),
+        unread: 0,
+        latest_event: None,
+        last_viewed: None,
+    }
+}
+
+// Joins a room by ID or alias, the room list is updated by
+// the next sync
+pub async fn join_room(client: &MatrixClient, context: &Context, room: &str) {
+    let id = match RoomIdOrAliasId::try_from(room) {
+        Ok(id) => id,
+        Err(why) => {
+            let error = ClientError::new(
+                "Unable to join room.",
+                ClientErrorKind::Other,
+                why,
+            );
+            return send_error(context, error);
+        },
+    };
+
+    match client.join_room_by_id_or_alias(&id, &[]).await {
+        Ok(_) => {
+            info!("Joined {}", room);
+            let toast =
+                Toast::new(format!("Joined {}.", room), Severity::Success);
+            context.send_notification(Notification::ShowToast(toast));
+        },
+        Err(why) => send_error(
+            context,
+            ClientError::from_matrix("Unable to join room.", why),
+        ),
+    }
+}
+
+pub async fn send_message(
+    client: &MatrixClient,
+    context: &Context,
+    latest: &LatestEvents,
+    sent: &SentEvents,
+    room: &str,
+    body: String,
+    kind: MessageKind,
+) {
+    let joined = match joined_room(client, room) {
+        Ok(joined) => joined,
+        Err(why) => return send_error(context, why),
+    };
+
+    let content = match kind {
+        MessageKind::New => Ok(MessageEventContent::text_plain(body)),
+        MessageKind::Reply => match event_id(latest, room) {
+            Some(event_id) => related_content(json!({
+                "msgtype": "m.text",
+                "body": body,
+                "m.relates_to": {
+                    "m.in_reply_to": { "event_id": event_id },
+                },
+            })),
+            None => Err("Nothing has been received in the room."),
+        },
+        // The body outside of the new content is shown by
+        // clients which don't support edits
+        MessageKind::Edit => match event_id(sent, room) {
+            Some(event_id) => related_content(json!({
+                "msgtype": "m.text",
+                "body": format!("* {}", body),
+                "m.new_content": { "msgtype": "m.text", "body": body },
+                "m.relates_to": {
+                    "rel_type": "m.replace",
+                    "event_id": event_id,
+                },
+            })),
+            None => Err("Nothing has been sent to the room."),
+        },
+    };
+    let content = match content {
+        Ok(content) => AnyMessageEventContent::RoomMessage(content),
+        Err(why) => {
+            let error = ClientError::new(
+                "Unable to send message.",
+                ClientErrorKind::Other,
+                why,
+            );
+            return send_error(context, error);
+        },
+    };
+
+    match joined.send(content, None).await {
+        Ok(response) => {
+            info!("Sent {} to {}", response.event_id, room);
+            // Edits keep pointing at the original message
+            if kind != MessageKind::Edit {
+                if let Ok(mut sent) = sent.lock() {
+                    sent.insert(room.to_string(), response.event_id);
+                }
+            }
+        },
+        Err(why) => send_error(
+            context,
+            ClientError::from_matrix("Unable to send message.", why),
+        ),
+    }
+}
+
+// Redacts the last message sent to the room this session
+pub async fn redact(
+    client: &MatrixClient,
+    context: &Context,
+    sent: &SentEvents,
+    room: &str,
+) {
+    let joined = match joined_room(client, room) {
+        Ok(joined) => joined,
+        Err(why) => return send_error(context, why),
+    };
+
+    let event_id = sent.lock().ok().and_then(|sent| sent.get(room).cloned());
+    let event_id = match event_id {
+        Some(event_id) => event_id,
+        None => {
+            let error = ClientError::new(
+                "Unable to redact message.",
+                ClientErrorKind::Other,
+                "Nothing has been sent to the room.",
+            );
+            return send_error(context, error);
+        },
+    };
+
+    match joined.redact(&event_id, None, None).await {
+        Ok(_) => {
+            info!("Redacted {} in {}", event_id, room);
+            if let Ok(mut sent) = sent.lock() {
+                sent.remove(room);
+            }
+            let toast = Toast::new("Message redacted.", Severity::Success);
+            context.send_notification(Notification::ShowToast(toast));
+        },
+        Err(why) => send_error(
+            context,
+            ClientError::from_matrix("Unable to redact message.", why),
+        ),
+    }
+}
+
+pub async fn upload_file(
+    client: &MatrixClient,
+    context: &Context,
+    room: &str,
+    path: &Path,
+) {
+    let joined = match joined_room(client, room) {
+        Ok(joined) => joined,
+        Err(why) => return send_error(context, why),
+    };
+
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(why) => {
+            return send_error(
+                context,
+                ClientError::new(
+                    "Unable to open file.",
+                    ClientErrorKind::Other,
+                    why,
+                ),
+            )
+        },
+    };
+
+    let name = attachment_name(path);
+    match joined
+        .send_attachment(&name, &guess_mime(path), &mut file, None)
+        .await
+    {
+        Ok(response) => info!("Uploaded {} to {}", response.event_id, room),
+        Err(why) => send_error(
+            context,
+            ClientError::from_matrix("Unable to upload file.", why),
+        ),
+    }
+}
+
+// Sends a read receipt for the newest event in the room,
+// the unread count is updated by the next sync
+pub async fn mark_read(
+    client: &MatrixClient,
+    context: &Context,
+    latest: &LatestEvents,
+    room: &str,
+) {
+    let joined = match joined_room(client, room) {
+        Ok(joined) => joined,
+        Err(why) => return send_error(context, why),
+    };
+
+    let event_id = latest
+        .lock()
+        .ok()
+        .and_then(|latest| latest.get(room).cloned());
+    let event_id = match event_id {
+        Some(event_id) => event_id,
+        None => {
+            let error = ClientError::new(
+                "Unable to mark room as read.",
+                ClientErrorKind::Other,
+                "Nothing has been received in the room.",
+            );
+            return send_error(context, error);
+        },
+    };
+
+    if let Err(why) = joined.read_receipt(&event_id).await {
+        send_error(
+            context,
+            ClientError::from_matrix("Unable to mark room as read.", why),
+        );
+    }
+}
+
+fn event_id(
+    events: &Mutex<HashMap<String, EventId>>,
+    room: &str,
+) -> Option<String> {
+    events
+        .lock()
+        .ok()
+        .and_then(|events| events.get(room).map(ToString::to_string))
+}
+
+// Replies and edits are built from the JSON described by
+// the spec, the relation is deserialized along with the
+// message
+fn related_content(
+    content: Value,
+) -> Result<MessageEventContent, &'static str> {
+    serde_json::from_value(content).map_err(|_| "Unable to build the message.")
+}
+
+fn joined_room(
+    client: &MatrixClient,
+    room: &str,
+) -> Result<Joined, ClientError> {
+    RoomId::try_from(room)
+        .ok()
+        .and_then(|id| client.get_joined_room(&id))
+        .ok_or_else(|| {
+            ClientError::new(
+                "Unable to find room.",
+                ClientErrorKind::Other,
+                format!("Not joined to '{}'.", room),
+            )
+        })
+}
+
+fn send_error(context: &Context, error: ClientError) {
+    context.send_notification(Notification::ClientError(error));
+}
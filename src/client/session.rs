@@ -0,0 +1,79 @@
+use std::{
+    fs::{read_to_string, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use matrix_sdk::Session;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    error::{Error, Result},
+    fs::DATA_DIRECTORY,
+};
+
+const SESSION_FILE: &str = "session.json";
+
+// Saved after logging in so the headless commands can
+// reuse the same device without a password
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredSession {
+    pub homeserver: Url,
+    #[serde(flatten)]
+    pub session:    Session,
+}
+
+fn session_path() -> Result<PathBuf> {
+    let data_dir = DATA_DIRECTORY.as_ref().map_err(|_| {
+        Error::ConfigError("unable to get data directory".to_string())
+    })?;
+
+    Ok(data_dir.join(SESSION_FILE))
+}
+
+pub fn save_session(session: &StoredSession) -> Result<()> {
+    let text = serde_json::to_string_pretty(session).map_err(|why| {
+        Error::OtherError(format!("Unable to serialize session.\n{}", why))
+    })?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The access token is as good as a password
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(session_path()?)?;
+    // The mode is only used when creating the file, older
+    // files may have been readable by others
+    #[cfg(unix)]
+    {
+        use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+        file.set_permissions(Permissions::from_mode(0o600))?;
+    }
+    file.write_all(text.as_bytes())?;
+
+    Ok(())
+}
+
+// Nothing is stored until the first successful login
+pub fn load_session() -> Result<Option<StoredSession>> {
+    let path = session_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = read_to_string(&path)?;
+    let session = serde_json::from_str(&text).map_err(|why| {
+        Error::OtherError(format!(
+            "Unable to parse stored session at {}.\n{}",
+            path.display(),
+            why
+        ))
+    })?;
+
+    Ok(Some(session))
+}
@@ -0,0 +1,297 @@
+use std::{
+    collections::HashMap,
+    fs::{read_to_string, File},
+    io::Write,
+    path::{Path, PathBuf},
+    result::Result as StdResult,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use tracing::level_filters::LevelFilter;
+
+use crate::{
+    app::{
+        keymap::{Action, KeySequence},
+        theme::DEFAULT_THEME,
+    },
+    error::{Error, Result},
+    fs::CONFIG_DIRECTORY,
+};
+
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+pub const CONFIG_VERSION: u32 = 1;
+
+// Written to disk the first time the app is started
+// without a config file so users have something to
+// edit
+const DEFAULT_CONFIG: &str = r#"# Config schema version, used for migrations
+version = 1
+
+[app]
+# Time between ticks in milliseconds
+tick_rate = 100
+# Hide the help bar at the bottom of the screen
+hide_help = false
+# Use vim style normal and insert modes
+modal = false
+# Built in themes are dark, light, high-contrast and
+# 16-color, custom themes are loaded from the themes
+# directory next to this file
+theme = "dark"
+# Accept requests from other programs on a socket in the
+# runtime directory
+control_socket = true
+
+[client]
+# Include the underlying error in error popups
+verbose = false
+
+[log]
+# One of error, warn, info, debug or trace
+level = "info"
+# Number of daily log files to keep
+max_files = 7
+
+[keybindings]
+# Replaces the default keys of an action, keys can be
+# pressed in sequence by separating them with spaces
+# toggle_help = ["Ctrl+h"]
+# quit = ["Ctrl+d", "Ctrl+x Ctrl+c"]
+
+# Hooks run a shell command when an event matches. Events
+# are one of message, mention, direct, invite or keyword and
+# can be limited to some rooms by ID, alias or name. Details
+# are passed as KONOHA_* environment variables and as JSON
+# on stdin
+# [[hooks]]
+# command = "notify-send \"$KONOHA_SENDER\" \"$KONOHA_BODY\""
+# events = ["mention", "direct"]
+# keywords = []
+# rooms = []
+# # Seconds before the command is killed
+# timeout = 10
+# max_per_minute = 10
+"#;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(deserialize_with = "deserialize_version")]
+    pub version:     u32,
+    pub app:         AppConfig,
+    pub client:      ClientConfig,
+    pub log:         LogConfig,
+    pub keybindings: HashMap<Action, Vec<KeySequence>>,
+    pub hooks:       Vec<HookConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version:     CONFIG_VERSION,
+            app:         AppConfig::default(),
+            client:      ClientConfig::default(),
+            log:         LogConfig::default(),
+            keybindings: HashMap::new(),
+            hooks:       Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    #[serde(deserialize_with = "deserialize_tick_rate")]
+    pub tick_rate:      u64,
+    pub hide_help:      bool,
+    pub modal:          bool,
+    pub theme:          String,
+    pub control_socket: bool,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate:      100,
+            hide_help:      false,
+            modal:          false,
+            theme:          DEFAULT_THEME.to_string(),
+            control_socket: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    pub verbose: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    #[serde(deserialize_with = "deserialize_level")]
+    pub level:     LevelFilter,
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level:     LevelFilter::INFO,
+            max_files: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookTrigger {
+    Message,
+    // The user's ID or name is in the message
+    Mention,
+    // Any message in a direct chat
+    Direct,
+    Invite,
+    // One of the hook's keywords is in the message
+    Keyword,
+}
+
+impl HookTrigger {
+    // Same as the name used in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Message => "message",
+            Self::Mention => "mention",
+            Self::Direct => "direct",
+            Self::Invite => "invite",
+            Self::Keyword => "keyword",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    #[serde(deserialize_with = "deserialize_command")]
+    pub command:        String,
+    pub events:         Vec<HookTrigger>,
+    #[serde(default)]
+    pub keywords:       Vec<String>,
+    // Empty to match every room
+    #[serde(default)]
+    pub rooms:          Vec<String>,
+    #[serde(default = "default_hook_timeout")]
+    pub timeout:        u64,
+    #[serde(default = "default_hook_rate")]
+    pub max_per_minute: usize,
+}
+
+fn default_hook_timeout() -> u64 {
+    10
+}
+
+fn default_hook_rate() -> usize {
+    10
+}
+
+impl Config {
+    // Loads the config from `path` if specified, otherwise
+    // from the config directory. A default config will be
+    // created if the file in the config directory doesn't
+    // exist yet
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let path = default_path()?;
+                if !path.exists() {
+                    let mut file = File::create(&path)?;
+                    file.write_all(DEFAULT_CONFIG.as_bytes())?;
+                }
+
+                path
+            },
+        };
+
+        let text = read_to_string(&path).map_err(|why| {
+            Error::ConfigError(format!(
+                "Unable to read '{}'.\n{}",
+                path.display(),
+                why
+            ))
+        })?;
+
+        // toml includes the line and column in the error
+        // message so there's no need to add it manually
+        toml::from_str(&text).map_err(|why| {
+            Error::ConfigError(format!(
+                "Unable to parse '{}'.\n{}",
+                path.display(),
+                why
+            ))
+        })
+    }
+}
+
+pub fn default_path() -> Result<PathBuf> {
+    let config_dir = CONFIG_DIRECTORY.as_ref().map_err(|_| {
+        Error::ConfigError("unable to get config directory".to_string())
+    })?;
+
+    Ok(config_dir.join(CONFIG_FILE_NAME))
+}
+
+fn deserialize_version<'de, D>(deserializer: D) -> StdResult<u32, D::Error>
+where
+    D: Deserializer<'de>, {
+    let version = u32::deserialize(deserializer)?;
+    if version == 0 || version > CONFIG_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "unsupported config version {}, latest is {}",
+            version, CONFIG_VERSION
+        )));
+    }
+
+    Ok(version)
+}
+
+fn deserialize_tick_rate<'de, D>(deserializer: D) -> StdResult<u64, D::Error>
+where
+    D: Deserializer<'de>, {
+    let tick_rate = u64::deserialize(deserializer)?;
+    if !(10..=1000).contains(&tick_rate) {
+        return Err(serde::de::Error::custom(format!(
+            "tick_rate should be between 10 and 1000, got {}",
+            tick_rate
+        )));
+    }
+
+    Ok(tick_rate)
+}
+
+fn deserialize_command<'de, D>(deserializer: D) -> StdResult<String, D::Error>
+where
+    D: Deserializer<'de>, {
+    let command = String::deserialize(deserializer)?;
+    if command.trim().is_empty() {
+        return Err(serde::de::Error::custom("hook command can't be empty"));
+    }
+
+    Ok(command)
+}
+
+fn deserialize_level<'de, D>(
+    deserializer: D,
+) -> StdResult<LevelFilter, D::Error>
+where
+    D: Deserializer<'de>, {
+    let level = String::deserialize(deserializer)?;
+    level.parse().map_err(|_| {
+        serde::de::Error::custom(format!(
+            "level should be one of error, warn, info, debug or trace, got \
+             '{}'",
+            level
+        ))
+    })
+}
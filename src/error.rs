@@ -1,19 +1,36 @@
 use std::{
     error::Error as StdError,
     fmt::{self, Write},
-    io::{stdout, Error as IoError},
+    io::Error as IoError,
     panic::PanicInfo,
     result::Result as StdResult,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
 };
 
 use backtrace::Backtrace;
-use crossterm::{
-    event::DisableMouseCapture,
-    execute,
-    terminal::{disable_raw_mode, LeaveAlternateScreen},
+use lazy_static::lazy_static;
+
+use crate::{
+    fs::{save_log, LogType},
+    logging::{scrub, try_recent_logs},
 };
 
-use crate::fs::{save_log, LogType};
+// Number of log entries included in crash reports
+const CRASH_LOG_LINES: usize = 50;
+
+lazy_static! {
+    // Menu and popups which are open, updated by the app so
+    // it can be included in crash reports
+    static ref CURRENT_VIEW: Mutex<String> = Mutex::new(String::new());
+}
+
+// Set while the app's main loop runs so a panic on another
+// thread stops the app instead of exiting straight away
+static APP_RUNNING: AtomicBool = AtomicBool::new(false);
+static PANICKED: AtomicBool = AtomicBool::new(false);
 
 pub type Result<T> = StdResult<T, Error>;
 
@@ -54,35 +71,77 @@ impl From<&str> for Error {
     }
 }
 
+pub fn set_current_view(view: String) {
+    if let Ok(mut current) = CURRENT_VIEW.lock() {
+        *current = view;
+    }
+}
+
+pub fn set_app_running(running: bool) {
+    APP_RUNNING.store(running, Ordering::SeqCst);
+}
+
+pub fn has_panicked() -> bool {
+    PANICKED.load(Ordering::SeqCst)
+}
+
+// Records the panic and returns whether the app will stop
+// itself, otherwise the process has to exit
+pub fn stop_app_on_panic() -> bool {
+    PANICKED.store(true, Ordering::SeqCst);
+    APP_RUNNING.load(Ordering::SeqCst)
+}
+
 // This was heavily inspired by https://crates.io/crates/human-panic
+// The terminal should be restored before this is called
 pub fn handle_panic(info: &PanicInfo) {
-    disable_raw_mode().expect("Unable to disable raw mode.");
-    execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture)
-        .expect("Unable to restore screen.");
-
     let name = env!("CARGO_PKG_NAME");
     let version = env!("CARGO_PKG_VERSION");
     let repo = env!("CARGO_PKG_REPOSITORY");
 
     let os_info = os_info::get().to_string();
 
-    let trace = get_trace();
+    // File paths in the location and trace may include the
+    // user's name
+    let trace = scrub(&get_trace());
+    let location = info.location().map_or_else(
+        || "Unknown".to_string(),
+        |location| scrub(&location.to_string()),
+    );
     let panic_message = info
         .message()
-        .map(|m| format!("{}", m))
+        .map(|m| scrub(&format!("{}", m)))
         .map_or_else(|| "Unknown".to_string(), |msg| msg);
 
+    // The lock may be held by the thread that panicked
+    let view = CURRENT_VIEW
+        .try_lock()
+        .map(|view| view.clone())
+        .unwrap_or_else(|_| "Unknown".to_string());
+
+    let logs = try_recent_logs();
+    let logs = logs[logs.len().saturating_sub(CRASH_LOG_LINES)..]
+        .iter()
+        .map(|entry| scrub(&entry.to_string()))
+        .collect::<Vec<String>>()
+        .join("\n");
+
     let mut buffer = String::new();
 
     let _ = write!(
         buffer,
         "Version: {}\n\
          System: {}\n\
+         View: {}\n\
          Cause: {}\n\
+         Location: {}\n\
+         \n\
+         Recent logs: \n\
+         {}\n\
          \n\
          Trace: \n\
          {}",
-        version, os_info, panic_message, trace
+        version, os_info, view, panic_message, location, logs, trace
     );
 
     let path = match save_log(LogType::Crash, buffer) {
@@ -110,13 +169,12 @@ pub fn handle_panic(info: &PanicInfo) {
          {}\n\
          \n\
          The report contains some basic information about your system\n\
-         like the OS and arch type, this can help with diagnosing what\n\
-         went wrong, if you don't want this to be sent feel free to\n\
-         remove it before submitting.\n",
-        name,
-        path,
-        name,
-        submit_url,
+         like the OS and arch type along with recent logs, this can help\n\
+         with diagnosing what went wrong. User IDs, room IDs, server\n\
+         names and message text have been removed but if there's\n\
+         anything else you don't want to be sent feel free to remove it\n\
+         before submitting.\n",
+        name, path, name, submit_url,
     );
 }
 
@@ -1,7 +1,8 @@
 use std::{
-    fs::{create_dir_all, File},
+    fs::{create_dir_all, read_dir, remove_file, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Mutex,
 };
 
 use chrono::Local;
@@ -10,30 +11,68 @@ use lazy_static::lazy_static;
 
 use crate::error::{Error, Result};
 
+// Older crash reports are removed when a new one is saved
+const MAX_CRASH_REPORTS: usize = 10;
+
 lazy_static! {
+    // Set from the command line before any of the
+    // directories are used
+    static ref PROFILE: Mutex<Option<String>> = Mutex::new(None);
     pub static ref CONFIG_DIRECTORY: Result<PathBuf> = {
         let path = dirs::config_dir()
             .ok_or_else(|| Error::ConfigError(String::new()))?
-            .join(format!(".{}", crate_name!()));
+            .join(format!(".{}", directory_name()));
 
         Ok(path)
     };
     pub static ref DATA_DIRECTORY: Result<PathBuf> = {
         let path = dirs::data_dir()
             .ok_or_else(|| Error::ConfigError(String::new()))?
-            .join(crate_name!());
+            .join(directory_name());
 
         Ok(path)
     };
     pub static ref CACHE_DIRECTORY: Result<PathBuf> = {
         let path = dirs::cache_dir()
             .ok_or_else(|| Error::ConfigError(String::new()))?
-            .join(crate_name!());
+            .join(directory_name());
 
         Ok(path)
     };
 }
 
+// Profiles get their own config, data and cache directories
+// so accounts and settings don't mix. This has to be called
+// before any of the directories are used
+pub fn set_profile(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(Error::ConfigError(format!(
+            "Invalid profile name '{}', only letters, numbers, '-' and '_' \
+             can be used.",
+            name
+        )));
+    }
+
+    if let Ok(mut profile) = PROFILE.lock() {
+        *profile = Some(name.to_string());
+    }
+
+    Ok(())
+}
+
+fn directory_name() -> String {
+    let profile = PROFILE.lock().ok().and_then(|profile| profile.clone());
+
+    match profile {
+        Some(profile) => format!("{}-{}", crate_name!(), profile),
+        None => crate_name!().to_string(),
+    }
+}
+
 pub fn create_directories() -> Result<()> {
     let config_dir = CONFIG_DIRECTORY.as_ref().map_err(|_| {
         Error::ConfigError("unable to get config directory".to_string())
@@ -59,14 +98,49 @@ pub fn create_directories() -> Result<()> {
     Ok(())
 }
 
-// More logs to come
+pub fn log_directory() -> Result<PathBuf> {
+    let data_dir = DATA_DIRECTORY.as_ref().map_err(|_| {
+        Error::ConfigError("unable to get data directory".to_string())
+    })?;
+
+    Ok(data_dir.join("logs"))
+}
+
+// Only the user should be able to open this as it holds the
+// control socket. Falls back to the cache directory when
+// there's no runtime directory, such as on macOS
+pub fn socket_directory() -> Result<PathBuf> {
+    match dirs::runtime_dir() {
+        Some(dir) => Ok(dir.join(directory_name())),
+        None => Ok(CACHE_DIRECTORY
+            .as_ref()
+            .map_err(|_| {
+                Error::ConfigError("unable to get cache directory".to_string())
+            })?
+            .join("run")),
+    }
+}
+
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(socket_directory()?.join(format!("{}.sock", crate_name!())))
+}
+
+// Kept next to the store which it protects
+pub fn lock_path() -> Result<PathBuf> {
+    let data_dir = DATA_DIRECTORY.as_ref().map_err(|_| {
+        Error::ConfigError("unable to get data directory".to_string())
+    })?;
+
+    Ok(data_dir.join(format!("{}.lock", crate_name!())))
+}
+
+// Regular logs are written by the logging module
 pub enum LogType {
     Crash,
 }
 
 pub fn save_log(log_type: LogType, log: String) -> Result<String> {
-    let mut dir = DATA_DIRECTORY.as_ref().unwrap().clone();
-    dir.push("logs");
+    let mut dir = log_directory()?;
 
     if !dir.exists() {
         create_dir_all(&dir)?;
@@ -76,7 +150,10 @@ pub fn save_log(log_type: LogType, log: String) -> Result<String> {
 
     match log_type {
         LogType::Crash => {
-            dir.push(format!("crash-report_{}.log", now));
+            let prefix = "crash-report_";
+            remove_old_files(&dir, prefix, MAX_CRASH_REPORTS - 1)?;
+
+            dir.push(format!("{}{}.log", prefix, now));
 
             let mut file = File::create(&dir)?;
             file.write_all(log.as_bytes())?;
@@ -85,3 +162,23 @@ pub fn save_log(log_type: LogType, log: String) -> Result<String> {
         },
     }
 }
+
+// Removes all but the newest `keep` files starting with
+// `prefix`, the date is included after the prefix so
+// sorting by name is enough
+pub fn remove_old_files(dir: &Path, prefix: &str, keep: usize) -> Result<()> {
+    let mut files = read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+
+    if files.len() > keep {
+        for path in &files[..files.len() - keep] {
+            remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
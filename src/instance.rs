@@ -0,0 +1,69 @@
+use std::{
+    fs::{read_to_string, File, OpenOptions},
+    io::Write,
+    process,
+};
+
+use fs2::{lock_contended_error, FileExt};
+
+use crate::{
+    error::{Error, Result},
+    fs::lock_path,
+};
+
+// Held until the process exits so that only one instance
+// uses the store for each profile, two instances sharing it
+// can corrupt the encryption sessions
+pub struct InstanceLock {
+    file: File,
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+// The lock is advisory and released by the OS when the
+// process exits, so a lock file left behind by a crash
+// isn't held by anything and is reused. Returns nothing if
+// another instance holds the lock
+pub fn try_lock() -> Result<Option<InstanceLock>> {
+    let path = lock_path()?;
+    // Truncated after locking so the running instance's ID
+    // isn't lost
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+
+    if let Err(why) = file.try_lock_exclusive() {
+        if why.raw_os_error() == lock_contended_error().raw_os_error() {
+            return Ok(None);
+        }
+
+        return Err(Error::OtherError(format!(
+            "Unable to lock '{}'.\n{}",
+            path.display(),
+            why
+        )));
+    }
+
+    // Only used to tell the user which process is running
+    file.set_len(0)?;
+    write!(file, "{}", process::id())?;
+
+    Ok(Some(InstanceLock {
+        file,
+    }))
+}
+
+// The ID of the process holding the lock, this may be
+// missing if it's still being written
+pub fn running_pid() -> Option<u32> {
+    let text = read_to_string(lock_path().ok()?).ok()?;
+
+    text.trim().parse().ok()
+}
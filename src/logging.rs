@@ -0,0 +1,385 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write as FmtWrite},
+    fs::create_dir_all,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::{DateTime, Local};
+use clap::crate_name;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{
+    fmt::Layer as FmtLayer,
+    layer::{Context, Layer, SubscriberExt},
+    util::SubscriberInitExt,
+    EnvFilter,
+};
+
+use crate::{
+    config::LogConfig,
+    error::{Error, Result},
+    fs::{log_directory, remove_old_files},
+};
+
+// Number of entries kept in memory for the log viewer
+const RECENT_LOG_SIZE: usize = 500;
+
+// Total number of entries logged, used to check for new
+// entries without locking the recent logs
+static LOG_COUNT: AtomicUsize = AtomicUsize::new(0);
+// Names of fields whose values should never be logged,
+// these are kept specific so errors such as
+// `M_UNKNOWN_TOKEN` aren't redacted
+const SECRET_KEYS: &str =
+    "access_token|refresh_token|password|passwd|client_secret";
+
+lazy_static! {
+    static ref RECENT_LOGS: Mutex<VecDeque<LogEntry>> =
+        Mutex::new(VecDeque::with_capacity(RECENT_LOG_SIZE));
+    // Kept here rather than by the caller so that the panic
+    // hook can flush the logs before exiting
+    static ref WORKER_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+    // Patterns which may contain credentials along with
+    // what they should be replaced with
+    static ref REDACT_PATTERNS: Vec<(Regex, &'static str)> = vec![
+        // Quoted values such as `password: "hunter2"`
+        (
+            Regex::new(&format!(
+                r#"(?i)((?:{})"?\s*[:=]\s*)"(?:[^"\\]|\\.)*""#,
+                SECRET_KEYS
+            ))
+            .unwrap(),
+            "${1}\"[REDACTED]\"",
+        ),
+        // Unquoted values such as `?access_token=abc123`
+        (
+            Regex::new(&format!(
+                r#"(?i)((?:{})\s*[:=]\s*)[^\s"&,}}]+"#,
+                SECRET_KEYS
+            ))
+            .unwrap(),
+            "${1}[REDACTED]",
+        ),
+        (
+            Regex::new(r"(?i)(bearer\s+)[A-Za-z0-9._~+/=-]+").unwrap(),
+            "${1}[REDACTED]",
+        ),
+        // Matrix access tokens
+        (
+            Regex::new(r"\bsy[a-z]_[A-Za-z0-9_]+").unwrap(),
+            "[REDACTED]",
+        ),
+    ];
+    // User, room and alias IDs along with event IDs which
+    // may not include a server name
+    static ref MATRIX_ID: Regex = Regex::new(
+        r"([@!#+$])[^\s:@!#$]+:[A-Za-z0-9.-]+(?::\d+)?|(\$)[A-Za-z0-9+/_-]{20,}"
+    )
+    .unwrap();
+    static ref SERVER_NAME: Regex =
+        Regex::new(r"\b(?:[A-Za-z0-9-]+\.)+[A-Za-z]{2,}\b").unwrap();
+    static ref QUOTED_TEXT: Regex =
+        Regex::new(r#""(?:[^"\\]|\\.)*""#).unwrap();
+}
+
+// File names which look like server names but should be
+// kept when scrubbing
+const FILE_EXTENSIONS: [&str; 5] = ["rs", "toml", "log", "json", "md"];
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time:    DateTime<Local>,
+    pub level:   Level,
+    pub target:  String,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:5} {}: {}",
+            self.time.format("%H:%M:%S"),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+// Replaces anything that looks like a password or token,
+// this is applied to everything written to the log files
+// and shown in the log viewer
+pub fn redact(text: &str) -> String {
+    REDACT_PATTERNS.iter().fold(
+        text.to_string(),
+        |text, (pattern, replacement)| {
+            pattern.replace_all(&text, *replacement).to_string()
+        },
+    )
+}
+
+// Removes personal data on top of redacting credentials,
+// used for crash reports which are meant to be shared.
+// Quoted text is removed as it may contain messages
+pub fn scrub(text: &str) -> String {
+    let mut text = redact(text);
+    // Home directories usually include the user's name, this
+    // shows up in file paths such as the panic location
+    if let Some(home) = dirs::home_dir() {
+        let home = home.to_string_lossy();
+        if home.len() > 1 {
+            text = text.replace(home.as_ref(), "~");
+        }
+    }
+    let text = MATRIX_ID.replace_all(&text, |caps: &Captures| {
+        let sigil = caps
+            .get(1)
+            .or_else(|| caps.get(2))
+            .map_or("", |m| m.as_str());
+        format!("{}[id]", sigil)
+    });
+    let text = QUOTED_TEXT.replace_all(&text, "\"[text]\"");
+
+    SERVER_NAME
+        .replace_all(&text, |caps: &Captures| {
+            let name = &caps[0];
+            let extension = name.rsplit('.').next().unwrap_or(name);
+
+            if FILE_EXTENSIONS.contains(&extension) {
+                name.to_string()
+            } else {
+                "[server]".to_string()
+            }
+        })
+        .to_string()
+}
+
+// Entries in the order they were logged
+pub fn recent_logs() -> Vec<LogEntry> {
+    RECENT_LOGS
+        .lock()
+        .map(|logs| logs.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+// Used by the panic hook, the lock may be held by the
+// thread that panicked so no logs are returned rather than
+// waiting for it
+pub fn try_recent_logs() -> Vec<LogEntry> {
+    RECENT_LOGS
+        .try_lock()
+        .map(|logs| logs.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn log_count() -> usize {
+    LOG_COUNT.load(Ordering::Relaxed)
+}
+
+// Flushes the logs when dropped, paths which exit the
+// process without unwinding call `flush` instead
+pub struct LogGuard;
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        flush();
+    }
+}
+
+// Writes any remaining logs and stops the writer, anything
+// logged afterwards is lost so this is called last
+pub fn flush() {
+    if let Ok(mut guard) = WORKER_GUARD.try_lock() {
+        guard.take();
+    }
+}
+
+// Starts writing logs to a new file in the logs directory
+// each day, the returned guard needs to be kept until
+// exiting
+pub fn init(config: &LogConfig) -> Result<LogGuard> {
+    let dir = log_directory()?;
+    if !dir.exists() {
+        create_dir_all(&dir)?;
+    }
+
+    let prefix = format!("{}.log", crate_name!());
+    // Today's file may not exist yet
+    remove_old_files(&dir, &prefix, config.max_files.saturating_sub(1))?;
+
+    let appender = rolling::daily(&dir, &prefix);
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    // RUST_LOG takes priority so that other crates can be
+    // debugged without changing the config
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new(format!("warn,{}={}", crate_name!(), config.level))
+    });
+    let file_layer = FmtLayer::default()
+        .with_ansi(false)
+        .with_writer(move || RedactWriter(writer.clone()));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(RecentLayer)
+        .try_init()
+        .map_err(|why| {
+            Error::OtherError(format!("Unable to start logging.\n{}", why))
+        })?;
+
+    if let Ok(mut worker_guard) = WORKER_GUARD.lock() {
+        *worker_guard = Some(guard);
+    }
+
+    Ok(LogGuard)
+}
+
+struct RedactWriter<W: Write>(W);
+
+impl<W: Write> Write for RedactWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = redact(&String::from_utf8_lossy(buf));
+        self.0.write_all(text.as_bytes())?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+// Keeps the most recent entries in memory for the log
+// viewer
+struct RecentLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let entry = LogEntry {
+            time:    Local::now(),
+            level:   *metadata.level(),
+            target:  metadata.target().to_string(),
+            message: redact(&visitor.to_string()),
+        };
+
+        if let Ok(mut logs) = RECENT_LOGS.lock() {
+            if logs.len() == RECENT_LOG_SIZE {
+                logs.pop_front();
+            }
+            logs.push_back(entry);
+        }
+        LOG_COUNT.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields:  String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+// The message followed by any other fields
+impl fmt::Display for MessageVisitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.message, self.fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_secret_keys() {
+        assert_eq!(
+            redact(r#"{"password": "hunter2", "user": "alice"}"#),
+            r#"{"password": "[REDACTED]", "user": "alice"}"#
+        );
+        assert_eq!(
+            redact("access_token=abc123&limit=10"),
+            "access_token=[REDACTED]&limit=10"
+        );
+        assert_eq!(
+            redact(r#"Client_Secret: "a \"quoted\" value""#),
+            r#"Client_Secret: "[REDACTED]""#
+        );
+    }
+
+    #[test]
+    fn redacts_tokens() {
+        assert_eq!(
+            redact("Authorization: Bearer abc.DEF-123"),
+            "Authorization: Bearer [REDACTED]"
+        );
+        assert_eq!(
+            redact("token syt_YWxpY2U_abcDEF_123 expired"),
+            "token [REDACTED] expired"
+        );
+        assert_eq!(redact("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn scrubs_matrix_ids() {
+        assert_eq!(
+            scrub("@alice:example.org joined !room:example.org"),
+            "@[id] joined ![id]"
+        );
+        assert_eq!(
+            scrub("#rust:matrix.org:8448 and $YUwRidLecu0123456789abcdef"),
+            "#[id] and $[id]"
+        );
+    }
+
+    #[test]
+    fn scrubs_quoted_text_and_servers() {
+        assert_eq!(
+            scrub(r#"Sent "see you at 5" via matrix.example.org"#),
+            r#"Sent "[text]" via [server]"#
+        );
+        // File names are kept so crash reports stay useful
+        assert_eq!(scrub("at src/app/mod.rs:42"), "at src/app/mod.rs:42");
+    }
+
+    #[test]
+    fn scrub_also_redacts() {
+        assert_eq!(scrub("password=hunter2"), "password=[REDACTED]");
+    }
+
+    #[test]
+    fn scrubs_home_directory() {
+        if let Some(home) =
+            dirs::home_dir().filter(|home| home.as_os_str().len() > 1)
+        {
+            let path = home.join("konoha").join("src").join("main.rs");
+            let scrubbed = scrub(&path.to_string_lossy());
+            assert!(!scrubbed.contains(home.to_string_lossy().as_ref()));
+            assert!(scrubbed.starts_with('~'));
+        }
+    }
+}
@@ -1,25 +1,411 @@
 #![feature(panic_info_message)]
 
-use crate::error::handle_panic;
+use std::{
+    io::{stderr, stdin, Write},
+    panic,
+    path::PathBuf,
+    process, thread,
+};
+
+use clap::{crate_name, crate_version, App, Arg, ArgMatches, SubCommand};
+use crossterm::tty::IsTty;
+
+use crate::{
+    app::{
+        control::{send_request, ControlRequest},
+        AppOptions,
+    },
+    client::{
+        auth::AuthCreds,
+        error::ClientError,
+        headless::{message_body, print_rooms, Command, RoomSummary},
+    },
+    error::{handle_panic, has_panicked, stop_app_on_panic, Error},
+};
 
 mod app;
 mod client;
+mod config;
 mod error;
 mod fs;
+mod instance;
+mod logging;
+mod terminal;
+
+// The revision is set by build.rs from Cargo.toml
+const VERSION: &str = concat!(
+    crate_version!(),
+    " (matrix-sdk ",
+    env!("MATRIX_SDK_REV"),
+    ")"
+);
 
 #[tokio::main]
 async fn main() {
-    if let Err(why) = run().await {
+    let result = run().await;
+    // The app stopped because another thread panicked
+    if has_panicked() {
+        process::exit(101);
+    }
+
+    if let Err(why) = result {
         eprintln!("{}", why);
+        process::exit(1);
     }
 }
 
 async fn run() -> error::Result<()> {
-    if std::env::var("RUST_BACKTRACE").is_err() {
-        std::panic::set_hook(Box::new(handle_panic));
+    // The terminal is restored before anything is printed,
+    // the default hook is still used if a backtrace is wanted
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        terminal::restore();
+
+        if std::env::var("RUST_BACKTRACE").is_err() {
+            handle_panic(info);
+        } else {
+            default_hook(info);
+        }
+
+        logging::flush();
+
+        // Panics on the main thread unwind and drop the app,
+        // which stops the client and closes the store. The
+        // app's main loop stops itself after a panic in the
+        // client task or one of the event threads, anything
+        // else would be left running without them so exits
+        let main_thread = thread::current().name() == Some("main");
+        if !main_thread && !stop_app_on_panic() {
+            process::exit(101);
+        }
+    }));
+
+    let matches = App::new(crate_name!())
+        .version(VERSION)
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("FILE")
+                .help("Use a custom config file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .short("p")
+                .long("profile")
+                .value_name("NAME")
+                .help("Use separate config, data and cache directories")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .help("Override the log level from the config")
+                .possible_values(&["error", "warn", "info", "debug", "trace"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .help("Include the underlying error in error popups"),
+        )
+        .arg(
+            Arg::with_name("no-mouse")
+                .long("no-mouse")
+                .help("Don't capture the mouse"),
+        )
+        .arg(
+            Arg::with_name("homeserver")
+                .long("homeserver")
+                .value_name("SERVER")
+                .help("Fill in the home server when logging in")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("user")
+                .short("u")
+                .long("user")
+                .value_name("USER")
+                .help("Fill in the username when logging in")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("forward")
+                .long("forward")
+                .help("Forward commands to a running instance without asking"),
+        )
+        .subcommand(
+            SubCommand::with_name("send")
+                .about("Send a message to a room")
+                .arg(
+                    Arg::with_name("room")
+                        .help("Room ID, alias or name")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("message")
+                        .help("Message to send, read from stdin if omitted"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rooms")
+                .about("List joined rooms")
+                .arg(json_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("tail")
+                .about(
+                    "Print new messages in a room as JSON lines, this can't \
+                     be run while the app is open",
+                )
+                .arg(
+                    Arg::with_name("room")
+                        .help("Room ID, alias or name")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("upload")
+                .about("Upload a file to a room")
+                .arg(
+                    Arg::with_name("room")
+                        .help("Room ID, alias or name")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("File to upload")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("whoami")
+                .about("Show the logged in user")
+                .arg(json_arg()),
+        )
+        .get_matches();
+
+    if let Some(profile) = matches.value_of("profile") {
+        fs::set_profile(profile)?;
     }
 
     fs::create_directories()?;
 
-    app::start_app()
+    // Only reads the stored session so it doesn't need the
+    // store or the lock
+    if let Some(Command::Whoami {
+        json,
+    }) = headless_command(&matches)
+    {
+        return client::headless::whoami(json)
+            .map_err(|why| headless_error(why, &matches));
+    }
+
+    // Released when the process exits, even through a panic
+    let _lock = match instance::try_lock()? {
+        Some(lock) => lock,
+        None => return forward_command(&matches),
+    };
+
+    // Subcommands reuse the session saved by the app and
+    // exit without starting the TUI
+    if let Some(command) = headless_command(&matches) {
+        return client::headless::run(command)
+            .await
+            .map_err(|why| headless_error(why, &matches));
+    }
+
+    // A full user ID also sets the home server
+    let mut credentials = AuthCreds::default();
+    if let Some(user) = matches.value_of("user") {
+        let user = user.trim_start_matches('@');
+        match user.split_once(':') {
+            Some((username, homeserver)) => {
+                credentials.username = username.to_string();
+                credentials.homeserver = homeserver.to_string();
+            },
+            None => credentials.username = user.to_string(),
+        }
+    }
+    if let Some(homeserver) = matches.value_of("homeserver") {
+        credentials.homeserver = homeserver.to_string();
+    }
+
+    // clap only accepts valid levels so parsing won't fail
+    let options = AppOptions {
+        config_path: matches.value_of("config").map(PathBuf::from),
+        log_level: matches
+            .value_of("log-level")
+            .and_then(|level| level.parse().ok()),
+        verbose: matches.is_present("verbose"),
+        no_mouse: matches.is_present("no-mouse"),
+        credentials,
+    };
+
+    app::start_app(options)
+}
+
+fn headless_error(why: ClientError, matches: &ArgMatches) -> Error {
+    let mut message = why.to_string();
+    if matches.is_present("verbose") {
+        message = format!("{}\n{}", message, why.details());
+    }
+
+    Error::OtherError(message)
+}
+
+// Called when another instance holds the lock, commands are
+// run by its client through the control socket. `tail`
+// needs a sync of its own so it can't be forwarded
+fn forward_command(matches: &ArgMatches) -> error::Result<()> {
+    let running = match instance::running_pid() {
+        Some(pid) => format!(
+            "{} is already running for this profile (PID {}).",
+            crate_name!(),
+            pid
+        ),
+        None => {
+            format!("{} is already running for this profile.", crate_name!())
+        },
+    };
+
+    // Rooms are only printed once the response arrives
+    let (request, rooms_json) = match headless_command(matches) {
+        Some(Command::Send {
+            room,
+            message,
+        }) => {
+            let body = message_body(message)
+                .map_err(|why| Error::OtherError(why.to_string()))?;
+            let request = ControlRequest::SendMessage {
+                room,
+                body,
+            };
+
+            (request, None)
+        },
+        Some(Command::Rooms {
+            json,
+        }) => (ControlRequest::Rooms, Some(json)),
+        // The running instance may have a different working
+        // directory
+        Some(Command::Upload {
+            room,
+            path,
+        }) => {
+            let path = path.canonicalize().map_err(|why| {
+                Error::OtherError(format!(
+                    "Unable to find '{}'.\n{}",
+                    path.display(),
+                    why
+                ))
+            })?;
+
+            (
+                ControlRequest::Upload {
+                    room,
+                    path,
+                },
+                None,
+            )
+        },
+        Some(Command::Tail {
+            ..
+        }) => {
+            return Err(Error::OtherError(format!(
+                "{}\n`tail` can't be forwarded to it, close it first.",
+                running
+            )))
+        },
+        // `whoami` never takes the lock
+        Some(Command::Whoami {
+            ..
+        })
+        | None => {
+            return Err(Error::OtherError(format!(
+                "{}\nClose it first or use a different profile with \
+                 --profile.",
+                running
+            )))
+        },
+    };
+
+    if !matches.is_present("forward") {
+        // Scripts can't answer the prompt
+        if !stdin().is_tty() || !stderr().is_tty() {
+            return Err(Error::OtherError(format!(
+                "{}\nUse --forward to run the command through it.",
+                running
+            )));
+        }
+
+        eprint!("{}\nRun the command through it? [y/N] ", running);
+        stderr().flush()?;
+        let mut answer = String::new();
+        stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+
+    let response = send_request(&request)?;
+    if !response.ok {
+        let error = response.error.unwrap_or_default();
+        return Err(Error::OtherError(format!(
+            "The running instance couldn't run the command.\n{}",
+            error
+        )));
+    }
+
+    if let Some(json) = rooms_json {
+        let rooms = response
+            .rooms
+            .unwrap_or_default()
+            .into_iter()
+            .map(|room| RoomSummary {
+                id:    room.id,
+                name:  room.name,
+                alias: room.alias,
+            })
+            .collect();
+        print_rooms(rooms, json)
+            .map_err(|why| Error::OtherError(why.to_string()))?;
+    }
+
+    Ok(())
+}
+
+fn json_arg() -> Arg<'static, 'static> {
+    Arg::with_name("json")
+        .long("json")
+        .help("Print the output as JSON")
+}
+
+fn headless_command(matches: &ArgMatches) -> Option<Command> {
+    // Required arguments are checked by clap
+    let command = match matches.subcommand() {
+        ("send", Some(args)) => Command::Send {
+            room:    args.value_of("room")?.to_string(),
+            message: args.value_of("message").map(ToString::to_string),
+        },
+        ("rooms", Some(args)) => Command::Rooms {
+            json: args.is_present("json"),
+        },
+        ("tail", Some(args)) => Command::Tail {
+            room: args.value_of("room")?.to_string(),
+        },
+        ("upload", Some(args)) => Command::Upload {
+            room: args.value_of("room")?.to_string(),
+            path: PathBuf::from(args.value_of("file")?),
+        },
+        ("whoami", Some(args)) => Command::Whoami {
+            json: args.is_present("json"),
+        },
+        _ => return None,
+    };
+
+    Some(command)
 }
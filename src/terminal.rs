@@ -0,0 +1,97 @@
+use std::{
+    io::stdout,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+#[cfg(unix)]
+use signal_hook::consts::SIGSTOP;
+
+use crate::error::{Error, Result};
+
+// Set while the terminal is in raw mode on the alternate
+// screen so it's only restored once
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+// Kept so the same state is entered when resuming
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(true);
+
+// Sets up the terminal for drawing and restores it when
+// dropped, which also happens when unwinding from a panic
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn new(mouse_capture: bool) -> Result<Self> {
+        MOUSE_CAPTURE.store(mouse_capture, Ordering::SeqCst);
+        enter()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+// Enables raw mode on the alternate screen along with mouse
+// capture unless it's been disabled
+pub fn enter() -> Result<()> {
+    enable_raw_mode().map_err(|why| {
+        Error::OtherError(format!("Unable to enable raw mode.\n{}", why))
+    })?;
+    ACTIVE.store(true, Ordering::SeqCst);
+
+    let mut result = execute!(stdout(), EnterAlternateScreen);
+    if result.is_ok() && MOUSE_CAPTURE.load(Ordering::SeqCst) {
+        result = execute!(stdout(), EnableMouseCapture);
+    }
+    if let Err(why) = result {
+        restore();
+
+        let message = format!("Unable to enter new screen.\n{}", why);
+        return Err(Error::OtherError(message));
+    }
+
+    Ok(())
+}
+
+// Restores the terminal and stops the process the same way
+// Ctrl+Z would outside of raw mode. This returns once the
+// process is continued, after the terminal is set up again
+#[cfg(unix)]
+pub fn suspend() -> Result<()> {
+    restore();
+
+    // SIGTSTP is handled by the app so SIGSTOP is used to
+    // actually stop the process
+    signal_hook::low_level::raise(SIGSTOP).map_err(|why| {
+        Error::OtherError(format!("Unable to suspend.\n{}", why))
+    })?;
+
+    enter()
+}
+
+// Job control isn't available so there's nothing to do
+#[cfg(not(unix))]
+pub fn suspend() -> Result<()> {
+    Ok(())
+}
+
+// Leaves the alternate screen and disables raw mode, this
+// is called from the panic hook and signal handler so any
+// errors are ignored as there's nothing left to do
+pub fn restore() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture);
+}